@@ -6,17 +6,59 @@ use self::instruction::*;
 use self::value::*;
 
 pub mod instruction;
+pub mod assembler;
+pub mod asm_parser;
+pub mod disassembler;
 mod expr_cgen;
 mod stmt_cgen;
 mod known_at_compile;
+mod scratch;
+mod runtime;
 mod value;
 
+pub use self::value::ValueLocation;
+pub use self::runtime::RuntimeRoutine;
+
+///Top level variables live from here up, the stack of the top level code starts after them
+pub const STACK_START: u16 = 0x4000;
+
+///Operands are copied to these fixed addresses before being operated on,
+///so the operation can address them with imr alone and keep the carry intact
+pub const SCRATCH_X: u16 = 0x3f00;
+pub const SCRATCH_Y: u16 = 0x3f08;
+///holds addresses that are only known at runtime
+pub const SCRATCH_P: u16 = 0x3f10;
+///the remainder of divisions and the product while multiplying
+pub const SCRATCH_Z: u16 = 0x3f18;
+///call site id, loop counter and sign flags of the runtime routines
+pub const RUNTIME_STATE: u16 = 0x3f20;
+
+///Statics and string literals used as pointers are stored from here up, below the scratch areas
+pub const DATA_START: u16 = 0x0100;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CodegenErr {
+    DataSegmentFull(u16), //SIZE of all string literals and statics
+}
+
+///a caller saves BPL, BPH and a 2 byte call site id right below the callee's frame,
+///the return value slot is right below that
+pub const SAVED_FRAME_SIZE: u16 = 4;
+
 pub struct CodeGenerator {
     ast: Vec<TypedStmt>,
     output: Vec<AssemblyCommand>,
     symbol_table: SymbolTable,
     defined_types: Vec<CustomType>,
     fn_templates: Vec<FnTemplate>,
+    label_count: usize,
+    loop_labels: Vec<(String, String)>, //(CONTINUE LABEL, END LABEL) of every enclosing loop
+    current_fn: Option<FnTemplate>,
+    call_sites: Vec<(String, String)>, //(CALLEE LABEL, RETURN LABEL)
+    runtime_routines: Vec<RuntimeRoutine>, //routines called so far, emitted after the functions
+    globals: Vec<(String, ValueType, u16)>,
+    data: Vec<(Vec<u8>, u16, bool)>, //(BYTES, ADDRESS, READ ONLY) written to memory before the top level code runs
+    data_size: u16,
 }
 
 impl CodeGenerator {
@@ -27,25 +69,267 @@ impl CodeGenerator {
             symbol_table: SymbolTable { symbols: vec![], frame_size: 0 },
             defined_types,
             fn_templates,
+            label_count: 0,
+            loop_labels: vec![],
+            current_fn: None,
+            call_sites: vec![],
+            runtime_routines: vec![],
+            globals: vec![],
+            data: vec![],
+            data_size: 0,
+        }
+    }
+
+    pub fn output(&self) -> &Vec<AssemblyCommand> {
+        &self.output
+    }
+
+    ///Output as textual assembly
+    pub fn output_text(&self) -> String {
+        write_asm(&self.output)
+    }
+
+    ///Top level variables with their fixed addresses
+    pub fn globals(&self) -> &Vec<(String, ValueType, u16)> {
+        &self.globals
+    }
+
+    /*
+    _start:
+        every byte of the data segment, statics first
+        top level statements, with BP at STACK_START
+        hlt
+
+    every function body
+
+    every function's return dispatch
+
+    every runtime routine that was called
+    */
+    pub fn generate(&mut self) -> Result<(), CodegenErr> {
+        let ast = self.ast.clone();
+
+        self.write_label("_start".to_string());
+
+        //statics have fixed addresses, every function can reach them
+        for stmt in ast.iter() {
+            if let TypedStmt::StaticDeclr(name, var_type, bytes) = stmt {
+                let address = self.new_data(bytes, false);
+
+                self.globals.push((name.clone(), var_type.clone(), address));
+                self.symbol_table.symbols.push(Symbol::Variable(name.clone(), var_type.clone(), ValueLocation::Absolute(address)));
+            }
+        }
+
+        let mut top_frame_size: u16 = 0;
+        for stmt in ast.iter() {
+            top_frame_size += stmt.locals_size(&self.defined_types);
+        }
+
+        self.write_instruction(Instruction::Imr(BPL, STACK_START as u8));
+        self.write_instruction(Instruction::Imr(BPH, (STACK_START >> 8) as u8));
+        self.write_instruction(Instruction::Imr(SPL, (STACK_START + top_frame_size) as u8));
+        self.write_instruction(Instruction::Imr(SPH, ((STACK_START + top_frame_size) >> 8) as u8));
+
+        for stmt in ast.iter() {
+            if !matches!(stmt, TypedStmt::FnDeclr(..)) {
+                stmt.generate(self);
+            }
+        }
+
+        self.write_instruction(Instruction::Hlt);
+
+        for stmt in ast.iter() {
+            if let TypedStmt::FnDeclr(template, params, body) = stmt {
+                self.generate_fn(template, params, body);
+            }
+        }
+
+        for template in self.fn_templates.clone() {
+            self.generate_return_dispatch(&template);
+        }
+
+        for routine in self.runtime_routines.clone() {
+            self.generate_runtime_routine(routine);
+        }
+
+        if DATA_START as usize + self.data_size as usize > SCRATCH_X as usize {
+            return Err(CodegenErr::DataSegmentFull(self.data_size))
         }
+
+        //the data is only known once everything is generated, it goes right after _start
+        let program_len = self.output.len();
+        for (bytes, address, _) in self.data.clone() {
+            for (n, byte) in bytes.into_iter().enumerate() {
+                self.write_instruction(Instruction::Imr(R0, byte));
+                self.str_reg_to_abs(R0, address + n as u16);
+            }
+        }
+
+        let data_init = self.output.split_off(program_len);
+        self.output.splice(1..1, data_init);
+
+        Ok(())
+    }
+
+    ///Address of `bytes` in the data segment, equal literals share their storage
+    fn data_address(&mut self, bytes: &[u8]) -> u16 {
+        if let Some((_, address, _)) = self.data.iter().find(|x| x.2 && x.0 == bytes) {
+            return *address
+        }
+
+        self.new_data(bytes, true)
+    }
+
+    ///Address of a copy of `bytes` in the data segment,
+    ///only read only copies can be shared
+    fn new_data(&mut self, bytes: &[u8], read_only: bool) -> u16 {
+        let address = DATA_START.wrapping_add(self.data_size);
+        self.data.push((bytes.to_vec(), address, read_only));
+        self.data_size = self.data_size.saturating_add(bytes.len() as u16);
+
+        address
+    }
+
+    /*
+    fn_NAME:
+        SP = BP + parameters + locals
+        body
+        jmp fn_NAME_return
+    */
+    fn generate_fn(&mut self, template: &FnTemplate, params: &TypedParameters, body: &TypedStmt) {
+        self.write_label(fn_label(&template.name));
+
+        let outer_frame_size = self.symbol_table.frame_size;
+        self.symbol_table.frame_size = 0;
+        self.symbol_table.symbols.push(Symbol::EnterScope);
+        self.current_fn = Some(template.clone());
+
+        for (param_name, param_type) in params.items.iter() {
+            self.declare_var(param_name.clone(), param_type.clone());
+        }
+
+        let frame_size = self.symbol_table.frame_size + body.locals_size(&self.defined_types);
+
+        self.write_instruction(Instruction::Ima(frame_size as u8));
+        self.write_instruction(Instruction::Add(BPL));
+        self.write_instruction(Instruction::Amov(SPL));
+        self.write_instruction(Instruction::Ima((frame_size >> 8) as u8));
+        self.write_instruction(Instruction::Adc(BPH));
+        self.write_instruction(Instruction::Amov(SPH));
+
+        body.generate(self);
+        self.write_instruction(Instruction::Jmp(fn_return_label(&template.name)));
+
+        self.current_fn = None;
+        self.leave_scope();
+        self.symbol_table.frame_size = outer_frame_size;
+    }
+
+    /*
+    fn_NAME_return:
+        R0, R1 = call site id, saved right below BP
+        call site dispatch
+    */
+    fn generate_return_dispatch(&mut self, template: &FnTemplate) {
+        self.write_label(fn_return_label(&template.name));
+
+        self.set_mar_bp_plus(-2i16 as u16);
+        self.write_instruction(Instruction::Ld(R0));
+        self.set_mar_bp_plus(-1i16 as u16);
+        self.write_instruction(Instruction::Ld(R1));
+
+        self.write_call_site_dispatch(&fn_label(&template.name));
+    }
+
+    /*
+    R0, R1 = call site id
+
+    for every call site:
+        ima id[0:7]
+        sub R0
+        bnz next
+        ima id[8:15]
+        sub R1
+        bze return_label
+        next:
+
+        hlt
+    */
+    fn write_call_site_dispatch(&mut self, callee: &str) {
+        let sites = self.call_sites.iter()
+            .filter(|x| x.0 == callee)
+            .map(|x| x.1.clone())
+            .collect::<Vec<String>>();
+
+        for (id, return_label) in sites.into_iter().enumerate() {
+            let next = self.new_label();
+
+            self.write_instruction(Instruction::Ima(id as u8));
+            self.write_instruction(Instruction::Sub(R0));
+            self.write_instruction(Instruction::Bnz(next.clone()));
+            self.write_instruction(Instruction::Ima((id >> 8) as u8));
+            self.write_instruction(Instruction::Sub(R1));
+            self.write_instruction(Instruction::Bze(return_label));
+            self.write_label(next);
+        }
+
+        self.write_instruction(Instruction::Hlt);
+    }
+
+    ///Registers a new call site of the code at `callee`, returns its id and return label
+    fn new_call_site(&mut self, callee: &str) -> (u16, String) {
+        let id = self.call_sites.iter().filter(|x| x.0 == callee).count() as u16;
+        let return_label = self.new_label();
+
+        self.call_sites.push((callee.to_string(), return_label.clone()));
+        (id, return_label)
     }
 
     fn write_instruction(&mut self, inst: Instruction) {
         self.output.push(AssemblyCommand::Instruction(inst));
     }
 
+    fn write_label(&mut self, label: String) {
+        self.output.push(AssemblyCommand::Label(label));
+    }
+
+    fn new_label(&mut self) -> String {
+        self.label_count += 1;
+        format!("L{}", self.label_count)
+    }
+
     fn declare_var(&mut self, name: String, v_type: ValueType) {
-        let var_size = v_type.size(&self.defined_types);
+        let location = self.reserve_var_space(&v_type);
+        self.declare_var_at(name, v_type, location);
+    }
+
+    ///Frame space for a variable that can't be seen yet
+    fn reserve_var_space(&mut self, v_type: &ValueType) -> ValueLocation {
+        let location = match self.current_fn {
+            Some(_) => ValueLocation::BpPlus(self.symbol_table.frame_size as i16),
+            None => ValueLocation::Absolute(STACK_START + self.symbol_table.frame_size),
+        };
+
+        self.symbol_table.frame_size += v_type.size(&self.defined_types);
+        location
+    }
+
+    fn declare_var_at(&mut self, name: String, v_type: ValueType, location: ValueLocation) {
+        if let ValueLocation::Absolute(address) = location {
+            if !self.symbol_table.symbols.contains(&Symbol::EnterScope) {
+                self.globals.push((name.clone(), v_type.clone(), address));
+            }
+        }
 
-        self.symbol_table.symbols.push(Symbol::Variable(name, v_type, self.symbol_table.frame_size));
-        self.symbol_table.frame_size += var_size;
+        self.symbol_table.symbols.push(Symbol::Variable(name, v_type, location));
     }
 
-    fn get_var(&self, target_name: String) -> (ValueType, u16) {
+    fn get_var(&self, target_name: String) -> (ValueType, ValueLocation) {
         for symbol in self.symbol_table.symbols.iter().rev() {
-            if let Symbol::Variable(name, v_type, offset_from_base) = symbol {
+            if let Symbol::Variable(name, v_type, location) = symbol {
                 if name == &target_name {
-                    return  (v_type.clone(), *offset_from_base)
+                    return (v_type.clone(), *location)
                 }
             }
         }
@@ -53,6 +337,19 @@ impl CodeGenerator {
         unreachable!()
     }
 
+    fn enter_scope(&mut self) {
+        self.symbol_table.symbols.push(Symbol::EnterScope);
+    }
+
+    ///frame space is not reused, so only the names go out of scope
+    fn leave_scope(&mut self) {
+        while let Some(symbol) = self.symbol_table.symbols.pop() {
+            if symbol == Symbol::EnterScope {
+                return
+            }
+        }
+    }
+
     fn increase_sp_by(&mut self, n: u16) {
         /*
         ima n[0:7]
@@ -64,6 +361,10 @@ impl CodeGenerator {
         amov SPH
         */
 
+        if n == 0 {
+            return
+        }
+
         self.write_instruction(Instruction::Ima(n as u8));
         self.write_instruction(Instruction::Add(SPL));
         self.write_instruction(Instruction::Amov(SPL));
@@ -72,7 +373,7 @@ impl CodeGenerator {
         self.write_instruction(Instruction::Adc(SPH));
         self.write_instruction(Instruction::Amov(SPH));
     }
-    
+
     ///needs to overwrite a register
     fn decrease_sp_by(&mut self, n: u16, overwrite: REG) {
         /*
@@ -87,18 +388,23 @@ impl CodeGenerator {
         amov SPH
         */
 
+        if n == 0 {
+            return
+        }
+
         self.write_instruction(Instruction::Imr(overwrite, n as u8));
         self.write_instruction(Instruction::Rmov(SPL));
         self.write_instruction(Instruction::Sub(overwrite));
         self.write_instruction(Instruction::Amov(SPL));
-        
+
         self.write_instruction(Instruction::Imr(overwrite, (n >> 8) as u8));
         self.write_instruction(Instruction::Rmov(SPH));
         self.write_instruction(Instruction::Sbc(overwrite));
         self.write_instruction(Instruction::Amov(SPH));
     }
 
-    fn ld_bp_plus_n_to_reg(&mut self, reg: REG, n: u16) {
+    ///n wraps around, so negative offsets work as well
+    fn set_mar_bp_plus(&mut self, n: u16) {
         /*
         ima n[0:7]
         add BPL
@@ -107,8 +413,6 @@ impl CodeGenerator {
         ima n[8:15]
         adc BPH
         amov MARH
-
-        ld REG
         */
 
         self.write_instruction(Instruction::Ima(n as u8));
@@ -118,72 +422,140 @@ impl CodeGenerator {
         self.write_instruction(Instruction::Ima((n >> 8) as u8));
         self.write_instruction(Instruction::Adc(BPH));
         self.write_instruction(Instruction::Amov(MARH));
+    }
 
+    fn ld_bp_plus_n_to_reg(&mut self, reg: REG, n: u16) {
+        self.set_mar_bp_plus(n);
         self.write_instruction(Instruction::Ld(reg));
     }
 
-    fn ld_sp_minus_to_reg(&mut self, reg: REG, n: u16) {
+    ///overwrites the register
+    fn set_mar_sp_minus(&mut self, n: u16, overwrite: REG) {
         /*
-        imr REG n[0:7]
+        imr OVERWRITE n[0:7]
         rmov SPL
-        sub REG
+        sub OVERWRITE
         amov MARL
 
-        imr REG n[8:15]
+        imr OVERWRITE n[8:15]
         rmov SPH
-        sbc REG
+        sbc OVERWRITE
         amov MARH
-
-        ld REG
         */
 
-        self.write_instruction(Instruction::Imr(reg, n as u8));
+        self.write_instruction(Instruction::Imr(overwrite, n as u8));
         self.write_instruction(Instruction::Rmov(SPL));
-        self.write_instruction(Instruction::Sub(reg));
+        self.write_instruction(Instruction::Sub(overwrite));
         self.write_instruction(Instruction::Amov(MARL));
-        
-        self.write_instruction(Instruction::Imr(reg, (n >> 8) as u8));
+
+        self.write_instruction(Instruction::Imr(overwrite, (n >> 8) as u8));
         self.write_instruction(Instruction::Rmov(SPH));
-        self.write_instruction(Instruction::Sbc(reg));
+        self.write_instruction(Instruction::Sbc(overwrite));
         self.write_instruction(Instruction::Amov(MARH));
+    }
 
+    fn ld_sp_minus_to_reg(&mut self, reg: REG, n: u16) {
+        self.set_mar_sp_minus(n, reg);
         self.write_instruction(Instruction::Ld(reg));
     }
 
-    ///loads two values to registers.
-    /// makes sure that the address that needs
-    /// to be written to is in the MAR
-    /// (in the case that one or more of the values is on top of the stack)
-    pub fn ld_nth_byte_two_values(&mut self, n: u16, values: (&Value, &Value), regs: (REG, REG)) {
-        let (x, y) = values;
-        
-        let mut n_on_stack: u8 = 0;
+    ///leaves every flag as is
+    fn set_mar_abs(&mut self, address: u16) {
+        self.write_instruction(Instruction::Imr(MARL, address as u8));
+        self.write_instruction(Instruction::Imr(MARH, (address >> 8) as u8));
+    }
 
-        if matches!(x.location, ValueLocation::SpMinus(_)) {
-            n_on_stack += 1;
-        }
+    fn ld_abs_to_reg(&mut self, reg: REG, address: u16) {
+        self.set_mar_abs(address);
+        self.write_instruction(Instruction::Ld(reg));
+    }
+
+    fn str_reg_to_abs(&mut self, reg: REG, address: u16) {
+        self.set_mar_abs(address);
+        self.write_instruction(Instruction::Str(reg));
+    }
+
+    ///MAR = the address stored at `ptr` + n,
+    ///overwrites R1
+    fn set_mar_ptr_plus(&mut self, ptr: u16, n: u16) {
+        /*
+        R1 = ptr[0:7] + n[0:7]
+        MARH = ptr[8:15] + n[8:15] + carry
+        MARL = R1
+        */
+
+        self.ld_abs_to_reg(R1, ptr);
+        self.write_instruction(Instruction::Ima(n as u8));
+        self.write_instruction(Instruction::Add(R1));
+        self.write_instruction(Instruction::Amov(R1));
+
+        self.ld_abs_to_reg(MARH, ptr + 1);
+        self.write_instruction(Instruction::Ima((n >> 8) as u8));
+        self.write_instruction(Instruction::Adc(MARH));
+        self.write_instruction(Instruction::Amov(MARH));
 
-        if matches!(y.location, ValueLocation::SpMinus(_)) {
-            n_on_stack += 1;
+        self.write_instruction(Instruction::Rmov(R1));
+        self.write_instruction(Instruction::Amov(MARL));
+    }
+
+    ///MAR = location + n, location can't be on the stack
+    fn set_mar_location_plus(&mut self, location: ValueLocation, n: u16) {
+        match location {
+            ValueLocation::BpPlus(offset) => self.set_mar_bp_plus((offset as u16).wrapping_add(n)),
+
+            ValueLocation::Absolute(address) => self.set_mar_abs(address.wrapping_add(n)),
+
+            _ => unreachable!()
         }
+    }
 
-        match n_on_stack {
-            0 => {
+    fn push_reg(&mut self, reg: REG) {
+        self.write_instruction(Instruction::Push(reg));
+        self.increase_sp_by(1);
+    }
 
-            }
+    ///overwrites R0
+    fn push_imm(&mut self, value: u8) {
+        self.write_instruction(Instruction::Imr(R0, value));
+        self.push_reg(R0);
+    }
+
+    fn drop_from_stack(&mut self, size: u16) {
+        self.decrease_sp_by(size, R0);
+    }
+
+    ///copies the `size` bytes on top of the stack to a location, leaving them on the stack
+    fn store_stack_top_to_location(&mut self, location: ValueLocation, size: u16) {
+        for nth in 0..size {
+            self.ld_sp_minus_to_reg(R0, size - nth);
+            self.set_mar_location_plus(location, nth);
+            self.write_instruction(Instruction::Str(R0));
         }
+    }
 
+    ///pops a u8 condition and sets the zero flag if it is false
+    fn pop_condition(&mut self) {
+        self.ld_sp_minus_to_reg(R0, 1);
+        self.decrease_sp_by(1, R1);
+        self.write_instruction(Instruction::Rmov(R0));
+    }
+}
 
-    } 
+pub fn fn_label(name: &str) -> String {
+    format!("fn_{}", name)
 }
 
+pub fn fn_return_label(name: &str) -> String {
+    format!("fn_{}_return", name)
+}
 
 pub struct SymbolTable {
     pub symbols: Vec<Symbol>,
     pub frame_size: u16,
 }
 
+#[derive(PartialEq)]
 pub enum Symbol {
     EnterScope,
-    Variable(String, ValueType, u16),
-}
\ No newline at end of file
+    Variable(String, ValueType, ValueLocation),
+}
@@ -0,0 +1,257 @@
+//! Reads textual assembly back into `AssemblyCommand`s.
+//!
+//! One command per line, in the format `write_asm` emits:
+//! - `name:` is a label, an instruction may follow it on the same line
+//! - `;` starts a comment, a line holding only a comment is kept as
+//!   `AssemblyCommand::Comment`, trailing comments are dropped
+//! - operands are separated by whitespace, registers are named as in
+//!   `REG_NAMES` and immediates are decimal or `0x` hexadecimal bytes
+//! - `.byte` takes an immediate and writes it as data, branches take a
+//!   label or a `0x` hexadecimal address
+
+use super::assembler::raw_address;
+use super::instruction::*;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum AsmParseErr {
+    UnknownMnemonic(Pos, String), //(POSITION, MNEMONIC)
+    UnknownRegister(Pos, String),
+    InvalidImmediate(Pos, String),
+    InvalidLabel(Pos, String),
+    WrongOperandCount(Pos, String),
+}
+
+///line and column of the word an error points at, both start at 1
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Pos {
+    pub line: usize,
+    pub col: usize,
+}
+
+impl AsmParseErr {
+    pub fn pos(&self) -> Pos {
+        match self {
+            Self::UnknownMnemonic(pos, _) => *pos,
+            Self::UnknownRegister(pos, _) => *pos,
+            Self::InvalidImmediate(pos, _) => *pos,
+            Self::InvalidLabel(pos, _) => *pos,
+            Self::WrongOperandCount(pos, _) => *pos,
+        }
+    }
+}
+
+///a line of the source, to find where the words sliced out of it start
+struct SourceLine<'a> {
+    text: &'a str,
+    number: usize,
+}
+
+impl SourceLine<'_> {
+    ///`word` must be a slice of `self.text`
+    fn pos_of(&self, word: &str) -> Pos {
+        Pos {
+            line: self.number,
+            col: word.as_ptr() as usize - self.text.as_ptr() as usize + 1,
+        }
+    }
+}
+
+pub fn parse_asm(text: &str) -> Result<Vec<AssemblyCommand>, AsmParseErr> {
+    let mut commands: Vec<AssemblyCommand> = vec![];
+
+    for (n, line) in text.lines().enumerate() {
+        let source = SourceLine { text: line, number: n + 1 };
+        let mut rest = line.trim();
+
+        if let Some(comment) = rest.strip_prefix(';') {
+            commands.push(AssemblyCommand::Comment(comment.trim().to_string()));
+            continue
+        }
+
+        if let Some(comment_start) = rest.find(';') {
+            rest = rest[..comment_start].trim();
+        }
+
+        if let Some(colon) = rest.find(':') {
+            let label = rest[..colon].trim();
+
+            if !is_label(label) {
+                return Err(AsmParseErr::InvalidLabel(source.pos_of(label), label.to_string()))
+            }
+
+            commands.push(AssemblyCommand::Label(label.to_string()));
+            rest = rest[colon + 1..].trim();
+        }
+
+        if rest.is_empty() {
+            continue
+        }
+
+        if let Some(operand) = rest.strip_prefix(".byte") {
+            let operand = operand.trim();
+            commands.push(AssemblyCommand::Byte(parse_imm(operand, &source)?));
+            continue
+        }
+
+        let inst = parse_instruction(rest, &source)?;
+        commands.push(AssemblyCommand::Instruction(inst));
+    }
+
+    Ok(commands)
+}
+
+fn parse_instruction(text: &str, line: &SourceLine) -> Result<Instruction, AsmParseErr> {
+    let mut words = text.split_whitespace();
+    let mnemonic_word = words.next().expect("line is not empty");
+    let mnemonic = mnemonic_word.to_lowercase();
+    let operands: Vec<&str> = words.collect();
+
+    let expected_operands = match mnemonic.as_str() {
+        "stc" | "clc" | "zac" | "spc" | "nop" | "hlt" => 0,
+        "imr" => 2,
+        _ => 1,
+    };
+
+    let inst = match mnemonic.as_str() {
+        "stc" => Instruction::Stc,
+        "clc" => Instruction::Clc,
+        "zac" => Instruction::Zac,
+        "spc" => Instruction::Spc,
+        "nop" => Instruction::Nop,
+        "hlt" => Instruction::Hlt,
+
+        _ => {
+            if operands.len() != expected_operands {
+                return Err(AsmParseErr::WrongOperandCount(line.pos_of(text), text.to_string()))
+            }
+
+            let first = operands[0];
+
+            match mnemonic.as_str() {
+                "add" => Instruction::Add(parse_reg(first, line)?),
+                "sub" => Instruction::Sub(parse_reg(first, line)?),
+                "adc" => Instruction::Adc(parse_reg(first, line)?),
+                "sbc" => Instruction::Sbc(parse_reg(first, line)?),
+                "ror" => Instruction::Ror(parse_reg(first, line)?),
+                "nor" => Instruction::Nor(parse_reg(first, line)?),
+                "and" => Instruction::And(parse_reg(first, line)?),
+                "rmov" => Instruction::Rmov(parse_reg(first, line)?),
+                "amov" => Instruction::Amov(parse_reg(first, line)?),
+                "str" => Instruction::Str(parse_reg(first, line)?),
+                "ld" => Instruction::Ld(parse_reg(first, line)?),
+                "in" => Instruction::In(parse_reg(first, line)?),
+                "out" => Instruction::Out(parse_reg(first, line)?),
+                "push" => Instruction::Push(parse_reg(first, line)?),
+                "pop" => Instruction::Pop(parse_reg(first, line)?),
+
+                "jmp" => Instruction::Jmp(parse_label(first, line)?),
+                "bca" => Instruction::Bca(parse_label(first, line)?),
+                "bnc" => Instruction::Bnc(parse_label(first, line)?),
+                "bze" => Instruction::Bze(parse_label(first, line)?),
+                "bnz" => Instruction::Bnz(parse_label(first, line)?),
+                "bsi" => Instruction::Bsi(parse_label(first, line)?),
+                "bpa" => Instruction::Bpa(parse_label(first, line)?),
+
+                "ima" => Instruction::Ima(parse_imm(first, line)?),
+                "imr" => Instruction::Imr(parse_reg(first, line)?, parse_imm(operands[1], line)?),
+
+                _ => return Err(AsmParseErr::UnknownMnemonic(line.pos_of(mnemonic_word), mnemonic))
+            }
+        }
+    };
+
+    if operands.len() != expected_operands {
+        return Err(AsmParseErr::WrongOperandCount(line.pos_of(text), text.to_string()))
+    }
+
+    Ok(inst)
+}
+
+fn parse_reg(text: &str, line: &SourceLine) -> Result<REG, AsmParseErr> {
+    match REG_NAMES.iter().position(|x| x.eq_ignore_ascii_case(text)) {
+        Some(reg) => Ok(reg as REG),
+        None => Err(AsmParseErr::UnknownRegister(line.pos_of(text), text.to_string()))
+    }
+}
+
+fn parse_imm(text: &str, line: &SourceLine) -> Result<u8, AsmParseErr> {
+    let parsed = match text.strip_prefix("0x").or_else(|| text.strip_prefix("0X")) {
+        Some(hex) => u8::from_str_radix(hex, 16),
+        None => text.parse::<u8>(),
+    };
+
+    match parsed {
+        Ok(t) => Ok(t),
+        Err(_) => Err(AsmParseErr::InvalidImmediate(line.pos_of(text), text.to_string()))
+    }
+}
+
+fn parse_label(text: &str, line: &SourceLine) -> Result<LABEL, AsmParseErr> {
+    if !is_label(text) && raw_address(text).is_none() {
+        return Err(AsmParseErr::InvalidLabel(line.pos_of(text), text.to_string()))
+    }
+
+    Ok(text.to_string())
+}
+
+///labels are identifiers: letters, digits and '_', not starting with a digit
+fn is_label(text: &str) -> bool {
+    match text.chars().next() {
+        Some(first) if !first.is_ascii_digit() => {
+            text.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        }
+
+        _ => false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_asm() {
+        let text = "; start\n_start:\n    imr MARL 0x3f ; port\nloop: ima 255\n\n    bnz loop\n    out r0\n    hlt\n";
+
+        assert_eq!(parse_asm(text).unwrap(), vec![
+            AssemblyCommand::Comment("start".to_string()),
+            AssemblyCommand::Label("_start".to_string()),
+            AssemblyCommand::Instruction(Instruction::Imr(MARL, 0x3f)),
+            AssemblyCommand::Label("loop".to_string()),
+            AssemblyCommand::Instruction(Instruction::Ima(255)),
+            AssemblyCommand::Instruction(Instruction::Bnz("loop".to_string())),
+            AssemblyCommand::Instruction(Instruction::Out(R0)),
+            AssemblyCommand::Instruction(Instruction::Hlt),
+        ]);
+
+        assert_eq!(parse_asm(".byte 0xff\njmp 0x0001").unwrap(), vec![
+            AssemblyCommand::Byte(0xff),
+            AssemblyCommand::Instruction(Instruction::Jmp("0x0001".to_string())),
+        ]);
+
+        let pos = |line, col| Pos { line, col };
+        assert_eq!(parse_asm("nop\n  frob R0").unwrap_err(), AsmParseErr::UnknownMnemonic(pos(2, 3), "frob".to_string()));
+        assert_eq!(parse_asm("add R9").unwrap_err(), AsmParseErr::UnknownRegister(pos(1, 5), "R9".to_string()));
+        assert_eq!(parse_asm("x: ima 256").unwrap_err(), AsmParseErr::InvalidImmediate(pos(1, 8), "256".to_string()));
+        assert_eq!(parse_asm("hlt R0").unwrap_err(), AsmParseErr::WrongOperandCount(pos(1, 1), "hlt R0".to_string()));
+        assert_eq!(parse_asm("  imr R0").unwrap_err(), AsmParseErr::WrongOperandCount(pos(1, 3), "imr R0".to_string()));
+        assert_eq!(parse_asm("1x:").unwrap_err(), AsmParseErr::InvalidLabel(pos(1, 1), "1x".to_string()));
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let source = "
+            fn add(a: u16, b: u16) -> u16 {
+                return a + b;
+            }
+
+            let x: u16 = add(1 as u16, 2 as u16);";
+
+        let artifacts = crate::compile(source, &crate::CompileOptions { emit: crate::Emit::Asm }).unwrap();
+        let commands = artifacts.asm.unwrap();
+        let text = write_asm(&commands);
+
+        assert_eq!(parse_asm(&text).unwrap(), commands);
+        assert_eq!(write_asm(&parse_asm(&text).unwrap()), text);
+    }
+}
@@ -0,0 +1,279 @@
+//! Binary encoding of the instruction set.
+//!
+//! Every instruction starts with a one byte opcode. Instructions that take a
+//! register keep it in the low 3 bits of the opcode (`REG` 0-7, see the
+//! constants in `instruction.rs`). Immediates are one byte and branch
+//! targets are absolute two byte addresses, low byte first.
+//!
+//! | opcode      | instruction | operands        | size |
+//! |-------------|-------------|-----------------|------|
+//! | 0x00 + REG  | add REG     |                 | 1    |
+//! | 0x08 + REG  | sub REG     |                 | 1    |
+//! | 0x10 + REG  | adc REG     |                 | 1    |
+//! | 0x18 + REG  | sbc REG     |                 | 1    |
+//! | 0x20 + REG  | ror REG     |                 | 1    |
+//! | 0x28 + REG  | nor REG     |                 | 1    |
+//! | 0x30 + REG  | and REG     |                 | 1    |
+//! | 0x38 + REG  | rmov REG    |                 | 1    |
+//! | 0x40 + REG  | amov REG    |                 | 1    |
+//! | 0x48 + REG  | str REG     |                 | 1    |
+//! | 0x50 + REG  | ld REG      |                 | 1    |
+//! | 0x58 + REG  | in REG      |                 | 1    |
+//! | 0x60 + REG  | out REG     |                 | 1    |
+//! | 0x68 + REG  | push REG    |                 | 1    |
+//! | 0x70 + REG  | pop REG     |                 | 1    |
+//! | 0x78 + REG  | imr REG     | value           | 2    |
+//! | 0x80        | stc         |                 | 1    |
+//! | 0x81        | clc         |                 | 1    |
+//! | 0x82        | zac         |                 | 1    |
+//! | 0x83        | spc         |                 | 1    |
+//! | 0x84        | nop         |                 | 1    |
+//! | 0x85        | hlt         |                 | 1    |
+//! | 0x86        | ima         | value           | 2    |
+//! | 0x88        | jmp         | address lo, hi  | 3    |
+//! | 0x89        | bca         | address lo, hi  | 3    |
+//! | 0x8a        | bnc         | address lo, hi  | 3    |
+//! | 0x8b        | bze         | address lo, hi  | 3    |
+//! | 0x8c        | bnz         | address lo, hi  | 3    |
+//! | 0x8d        | bsi         | address lo, hi  | 3    |
+//! | 0x8e        | bpa         | address lo, hi  | 3    |
+//!
+//! Opcodes not listed are illegal.
+//!
+//! `.byte` writes a single data byte. Branches take a label, or a raw
+//! `0x` address when the target has no label.
+
+use std::collections::HashMap;
+
+use super::instruction::*;
+
+pub const OP_ADD: u8 = 0x00;
+pub const OP_SUB: u8 = 0x08;
+pub const OP_ADC: u8 = 0x10;
+pub const OP_SBC: u8 = 0x18;
+pub const OP_ROR: u8 = 0x20;
+pub const OP_NOR: u8 = 0x28;
+pub const OP_AND: u8 = 0x30;
+pub const OP_RMOV: u8 = 0x38;
+pub const OP_AMOV: u8 = 0x40;
+pub const OP_STR: u8 = 0x48;
+pub const OP_LD: u8 = 0x50;
+pub const OP_IN: u8 = 0x58;
+pub const OP_OUT: u8 = 0x60;
+pub const OP_PUSH: u8 = 0x68;
+pub const OP_POP: u8 = 0x70;
+pub const OP_IMR: u8 = 0x78;
+
+pub const OP_STC: u8 = 0x80;
+pub const OP_CLC: u8 = 0x81;
+pub const OP_ZAC: u8 = 0x82;
+pub const OP_SPC: u8 = 0x83;
+pub const OP_NOP: u8 = 0x84;
+pub const OP_HLT: u8 = 0x85;
+pub const OP_IMA: u8 = 0x86;
+
+pub const OP_JMP: u8 = 0x88;
+pub const OP_BCA: u8 = 0x89;
+pub const OP_BNC: u8 = 0x8a;
+pub const OP_BZE: u8 = 0x8b;
+pub const OP_BNZ: u8 = 0x8c;
+pub const OP_BSI: u8 = 0x8d;
+pub const OP_BPA: u8 = 0x8e;
+
+///opcodes below this one carry a register in their low 3 bits
+pub const REG_OPS_END: u8 = 0x80;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum AssembleErr {
+    UndefinedLabel(String),
+    DuplicateLabel(String),
+    ProgramTooLarge,
+}
+
+impl Instruction {
+    ///Size in bytes once encoded
+    pub fn encoded_size(&self) -> u16 {
+        match self {
+            Self::Imr(_, _) | Self::Ima(_) => 2,
+
+            Self::Jmp(_) | Self::Bca(_) | Self::Bnc(_) | Self::Bze(_) |
+            Self::Bnz(_) | Self::Bsi(_) | Self::Bpa(_) => 3,
+
+            _ => 1,
+        }
+    }
+
+    pub fn opcode(&self) -> u8 {
+        match self {
+            Self::Add(r) => OP_ADD | r,
+            Self::Sub(r) => OP_SUB | r,
+            Self::Adc(r) => OP_ADC | r,
+            Self::Sbc(r) => OP_SBC | r,
+            Self::Ror(r) => OP_ROR | r,
+            Self::Nor(r) => OP_NOR | r,
+            Self::And(r) => OP_AND | r,
+            Self::Rmov(r) => OP_RMOV | r,
+            Self::Amov(r) => OP_AMOV | r,
+            Self::Str(r) => OP_STR | r,
+            Self::Ld(r) => OP_LD | r,
+            Self::In(r) => OP_IN | r,
+            Self::Out(r) => OP_OUT | r,
+            Self::Push(r) => OP_PUSH | r,
+            Self::Pop(r) => OP_POP | r,
+            Self::Imr(r, _) => OP_IMR | r,
+
+            Self::Stc => OP_STC,
+            Self::Clc => OP_CLC,
+            Self::Zac => OP_ZAC,
+            Self::Spc => OP_SPC,
+            Self::Nop => OP_NOP,
+            Self::Hlt => OP_HLT,
+            Self::Ima(_) => OP_IMA,
+
+            Self::Jmp(_) => OP_JMP,
+            Self::Bca(_) => OP_BCA,
+            Self::Bnc(_) => OP_BNC,
+            Self::Bze(_) => OP_BZE,
+            Self::Bnz(_) => OP_BNZ,
+            Self::Bsi(_) => OP_BSI,
+            Self::Bpa(_) => OP_BPA,
+        }
+    }
+
+    pub fn branch_target(&self) -> Option<&LABEL> {
+        match self {
+            Self::Jmp(l) | Self::Bca(l) | Self::Bnc(l) | Self::Bze(l) |
+            Self::Bnz(l) | Self::Bsi(l) | Self::Bpa(l) => Some(l),
+
+            _ => None,
+        }
+    }
+
+    ///does nothing if this is not a branch
+    pub fn set_branch_target(&mut self, target: LABEL) {
+        match self {
+            Self::Jmp(l) | Self::Bca(l) | Self::Bnc(l) | Self::Bze(l) |
+            Self::Bnz(l) | Self::Bsi(l) | Self::Bpa(l) => *l = target,
+
+            _ => {}
+        }
+    }
+}
+
+///`0x001a` as a branch target is the address itself
+pub fn raw_address(target: &str) -> Option<u16> {
+    let hex = target.strip_prefix("0x")?;
+    u16::from_str_radix(hex, 16).ok()
+}
+
+///First pass, gives every label the address of the instruction following it
+pub fn label_addresses(commands: &[AssemblyCommand]) -> Result<HashMap<String, u16>, AssembleErr> {
+    let mut labels: HashMap<String, u16> = HashMap::new();
+    let mut address: u32 = 0;
+
+    for c in commands {
+        match c {
+            AssemblyCommand::Label(name) => {
+                if labels.contains_key(name) {
+                    return Err(AssembleErr::DuplicateLabel(name.clone()))
+                }
+
+                if address > u16::MAX as u32 {
+                    return Err(AssembleErr::ProgramTooLarge)
+                }
+
+                labels.insert(name.clone(), address as u16);
+            }
+
+            AssemblyCommand::Instruction(inst) => address += inst.encoded_size() as u32,
+
+            AssemblyCommand::Byte(_) => address += 1,
+
+            AssemblyCommand::Comment(_) => {}
+        }
+    }
+
+    if address > u16::MAX as u32 + 1 {
+        return Err(AssembleErr::ProgramTooLarge)
+    }
+
+    Ok(labels)
+}
+
+///Encodes commands into a binary image starting at address 0
+pub fn assemble(commands: &[AssemblyCommand]) -> Result<Vec<u8>, AssembleErr> {
+    let labels = label_addresses(commands)?;
+    let mut image: Vec<u8> = vec![];
+
+    for c in commands {
+        if let AssemblyCommand::Byte(b) = c {
+            image.push(*b);
+        }
+
+        if let AssemblyCommand::Instruction(inst) = c {
+            image.push(inst.opcode());
+
+            match inst {
+                Instruction::Imr(_, value) | Instruction::Ima(value) => image.push(*value),
+
+                _ => {}
+            }
+
+            if let Some(target) = inst.branch_target() {
+                let address = match labels.get(target).copied().or_else(|| raw_address(target)) {
+                    Some(t) => t,
+                    None => return Err(AssembleErr::UndefinedLabel(target.clone()))
+                };
+
+                image.push(address as u8);
+                image.push((address >> 8) as u8);
+            }
+        }
+    }
+
+    Ok(image)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn inst(i: Instruction) -> AssemblyCommand {
+        AssemblyCommand::Instruction(i)
+    }
+
+    #[test]
+    fn test_assemble() {
+        let commands = vec![
+            AssemblyCommand::Comment("count down".to_string()),
+            inst(Instruction::Imr(R0, 3)),
+            AssemblyCommand::Label("loop".to_string()),
+            inst(Instruction::Rmov(R0)),
+            inst(Instruction::Bze("end".to_string())),
+            inst(Instruction::Jmp("loop".to_string())),
+            AssemblyCommand::Label("end".to_string()),
+            inst(Instruction::Hlt),
+        ];
+
+        assert_eq!(
+            assemble(&commands),
+            Ok(vec![0x78, 3, 0x38, 0x8b, 9, 0, 0x88, 2, 0, 0x85])
+        );
+    }
+
+    #[test]
+    fn test_label_errors() {
+        let undefined = vec![inst(Instruction::Jmp("nowhere".to_string()))];
+        assert_eq!(assemble(&undefined), Err(AssembleErr::UndefinedLabel("nowhere".to_string())));
+
+        let duplicate = vec![
+            AssemblyCommand::Label("a".to_string()),
+            inst(Instruction::Nop),
+            AssemblyCommand::Label("a".to_string()),
+        ];
+        assert_eq!(assemble(&duplicate), Err(AssembleErr::DuplicateLabel("a".to_string())));
+
+        let raw = vec![inst(Instruction::Jmp("0x1234".to_string())), AssemblyCommand::Byte(0xff)];
+        assert_eq!(assemble(&raw), Ok(vec![0x88, 0x34, 0x12, 0xff]));
+    }
+}
@@ -0,0 +1,207 @@
+//! Turns binary images back into instructions, the inverse of `assemble`.
+//!
+//! Branch targets get synthesized labels named after their address,
+//! `L_001a` for 0x001a, placed right before the instruction they point to.
+//! Bytes that don't decode become `.byte` data and branches into the middle
+//! of an instruction or data keep their raw address.
+
+use std::collections::BTreeSet;
+
+use super::assembler::*;
+use super::instruction::*;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DisassembleErr {
+    IllegalOpcode(u16, u8), //(ADDRESS, OPCODE)
+    Truncated(u16), //ADDRESS of the instruction missing operand bytes
+}
+
+pub fn target_label(address: u16) -> LABEL {
+    format!("L_{:04x}", address)
+}
+
+///Decodes the instruction at `address`, branches get the label of their target
+pub fn decode(image: &[u8], address: u16) -> Result<Instruction, DisassembleErr> {
+    let operand = |n: u16| -> Result<u8, DisassembleErr> {
+        match image.get(address as usize + n as usize) {
+            Some(t) => Ok(*t),
+            None => Err(DisassembleErr::Truncated(address))
+        }
+    };
+
+    let opcode = operand(0)?;
+
+    if opcode < REG_OPS_END {
+        let reg = opcode & 0b111;
+
+        let inst = match opcode & !0b111 {
+            OP_ADD => Instruction::Add(reg),
+            OP_SUB => Instruction::Sub(reg),
+            OP_ADC => Instruction::Adc(reg),
+            OP_SBC => Instruction::Sbc(reg),
+            OP_ROR => Instruction::Ror(reg),
+            OP_NOR => Instruction::Nor(reg),
+            OP_AND => Instruction::And(reg),
+            OP_RMOV => Instruction::Rmov(reg),
+            OP_AMOV => Instruction::Amov(reg),
+            OP_STR => Instruction::Str(reg),
+            OP_LD => Instruction::Ld(reg),
+            OP_IN => Instruction::In(reg),
+            OP_OUT => Instruction::Out(reg),
+            OP_PUSH => Instruction::Push(reg),
+            OP_POP => Instruction::Pop(reg),
+            OP_IMR => Instruction::Imr(reg, operand(1)?),
+
+            _ => unreachable!()
+        };
+
+        return Ok(inst)
+    }
+
+    let inst = match opcode {
+        OP_STC => Instruction::Stc,
+        OP_CLC => Instruction::Clc,
+        OP_ZAC => Instruction::Zac,
+        OP_SPC => Instruction::Spc,
+        OP_NOP => Instruction::Nop,
+        OP_HLT => Instruction::Hlt,
+        OP_IMA => Instruction::Ima(operand(1)?),
+
+        OP_JMP | OP_BCA | OP_BNC | OP_BZE | OP_BNZ | OP_BSI | OP_BPA => {
+            let label = target_label(u16::from_le_bytes([operand(1)?, operand(2)?]));
+
+            match opcode {
+                OP_JMP => Instruction::Jmp(label),
+                OP_BCA => Instruction::Bca(label),
+                OP_BNC => Instruction::Bnc(label),
+                OP_BZE => Instruction::Bze(label),
+                OP_BNZ => Instruction::Bnz(label),
+                OP_BSI => Instruction::Bsi(label),
+                OP_BPA => Instruction::Bpa(label),
+                _ => unreachable!()
+            }
+        }
+
+        _ => return Err(DisassembleErr::IllegalOpcode(address, opcode))
+    };
+
+    Ok(inst)
+}
+
+///Every command with the address it sits at, labels share the address of the instruction after them
+pub fn disassemble(image: &[u8]) -> Vec<(u16, AssemblyCommand)> {
+    let mut decoded: Vec<(u16, AssemblyCommand)> = vec![];
+    let mut address: usize = 0;
+
+    while address < image.len() {
+        match decode(image, address as u16) {
+            Ok(inst) => {
+                let size = inst.encoded_size() as usize;
+                decoded.push((address as u16, AssemblyCommand::Instruction(inst)));
+                address += size;
+            }
+
+            Err(_) => {
+                decoded.push((address as u16, AssemblyCommand::Byte(image[address])));
+                address += 1;
+            }
+        }
+    }
+
+    //the end of the image is a valid target as well
+    let starts = decoded.iter()
+        .filter(|x| matches!(x.1, AssemblyCommand::Instruction(_)))
+        .map(|x| x.0 as usize)
+        .chain(std::iter::once(image.len()))
+        .collect::<BTreeSet<usize>>();
+
+    let mut targets: BTreeSet<u16> = BTreeSet::new();
+    for (address, c) in decoded.iter_mut() {
+        if let AssemblyCommand::Instruction(inst) = c {
+            if inst.branch_target().is_none() {
+                continue
+            }
+
+            let target = u16::from_le_bytes([image[*address as usize + 1], image[*address as usize + 2]]);
+
+            if starts.contains(&(target as usize)) {
+                targets.insert(target);
+            } else {
+                inst.set_branch_target(format!("{:#06x}", target));
+            }
+        }
+    }
+
+    let mut commands: Vec<(u16, AssemblyCommand)> = vec![];
+    for (address, c) in decoded {
+        if targets.contains(&address) && matches!(c, AssemblyCommand::Instruction(_)) {
+            commands.push((address, AssemblyCommand::Label(target_label(address))));
+        }
+
+        commands.push((address, c));
+    }
+
+    let end = image.len() as u16;
+    if targets.contains(&end) {
+        commands.push((end, AssemblyCommand::Label(target_label(end))));
+    }
+
+    commands
+}
+
+///Assembly text with the address of every instruction in a trailing comment,
+///reads back with `parse_asm`
+pub fn write_listing(commands: &[(u16, AssemblyCommand)]) -> String {
+    let mut out = String::new();
+
+    for (address, c) in commands {
+        match c {
+            AssemblyCommand::Instruction(_) | AssemblyCommand::Byte(_) => out.push_str(&format!("{:<20}; {:#06x}\n", c.to_string(), address)),
+
+            _ => out.push_str(&format!("{}\n", c)),
+        }
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cgen::asm_parser::parse_asm;
+
+    #[test]
+    fn test_disassemble() {
+        let text = "imr R0 3\nimr MARL 0x01\nloop: out R0\nrmov R0\nima 1\nsub R0\nbnz loop\njmp end\nhlt\nend:\n";
+        let image = assemble(&parse_asm(text).unwrap()).unwrap();
+
+        let commands = disassemble(&image);
+        assert_eq!(commands[2], (4, AssemblyCommand::Label("L_0004".to_string())));
+        assert_eq!(commands[7], (9, AssemblyCommand::Instruction(Instruction::Bnz("L_0004".to_string()))));
+        assert_eq!(commands.last().unwrap(), &(16, AssemblyCommand::Label("L_0010".to_string())));
+
+        //the listing assembles back to the same image
+        let listing = write_listing(&commands);
+        assert_eq!(assemble(&parse_asm(&listing).unwrap()).unwrap(), image);
+
+        assert_eq!(decode(&[0xff], 0), Err(DisassembleErr::IllegalOpcode(0, 0xff)));
+        assert_eq!(decode(&[OP_NOP, OP_IMA], 1), Err(DisassembleErr::Truncated(1)));
+    }
+
+    #[test]
+    fn test_disassemble_data() {
+        //an illegal byte, a branch into the middle of ima and a truncated ima
+        let image = [0xff, OP_IMA, 0, OP_JMP, 2, 0, OP_IMA];
+
+        let commands = disassemble(&image);
+        assert_eq!(commands, vec![
+            (0, AssemblyCommand::Byte(0xff)),
+            (1, AssemblyCommand::Instruction(Instruction::Ima(0))),
+            (3, AssemblyCommand::Instruction(Instruction::Jmp("0x0002".to_string()))),
+            (6, AssemblyCommand::Byte(OP_IMA)),
+        ]);
+
+        let listing = write_listing(&commands);
+        assert_eq!(assemble(&parse_asm(&listing).unwrap()).unwrap(), image);
+    }
+}
@@ -6,166 +6,678 @@ impl TypedExpr {
     ///Value which the Expr eventaully evaluates to
     ///is placed on the top of the stack
     pub fn generate_onto_stack(&self, cg: &mut CodeGenerator) {
+        if self.value_known_at_compile() {
+            for nth in 0..self.final_type().size(&cg.defined_types) {
+                cg.push_imm(self.get_nth_byte(nth));
+            }
+
+            return
+        }
+
         match self {
-            TypedExpr::Term(final_type, binary_expr) => {
-                let (left, op, right) = (&binary_expr.left, &binary_expr.operator, &binary_expr.right);
+            TypedExpr::Term(final_type, binary_expr) | TypedExpr::Factor(final_type, binary_expr) => {
+                let size = final_type.size(&cg.defined_types);
+                generate_binary_to_scratch(cg, binary_expr);
+
+                let result = scratch_operation(cg, &binary_expr.operator, final_type);
+                cg.push_scratch(result, size);
+            }
+
+            TypedExpr::Equality(_, binary_expr) => {
+                let size = binary_expr.left.final_type().size(&cg.defined_types);
+                generate_binary_to_scratch(cg, binary_expr);
+
+                let not_equal = cg.new_label();
+                cg.scratch_equal(SCRATCH_X, SCRATCH_Y, size, &not_equal);
+
+                let equal_result = (binary_expr.operator == "==") as u8;
+                push_bool_result(cg, not_equal, equal_result);
+            }
+
+            TypedExpr::Comparison(_, binary_expr) => {
+                let op = binary_expr.operator.as_str();
 
-                let (left_value, right_value, stack_increased_by) = create_two_values(cg, left, right);
+                if op == "&&" || op == "||" {
+                    generate_logical(cg, binary_expr);
+                    return
+                }
+
+                let operand_type = binary_expr.left.final_type();
+                let size = operand_type.size(&cg.defined_types);
+                generate_binary_to_scratch(cg, binary_expr);
+
+                /*
+                x < y  : carry of x - y
+                x > y  : carry of y - x
+                x <= y : no carry of y - x
+                x >= y : no carry of x - y
+                */
+
+                let (x, y) = match op {
+                    "<" | ">=" => (SCRATCH_X, SCRATCH_Y),
+                    ">" | "<=" => (SCRATCH_Y, SCRATCH_X),
+                    _ => unreachable!()
+                };
+
+                cg.scratch_less_than(x, y, size, operand_type.is_signed_type());
 
-                let save_to_location: ValueLocation;
-                if stack_increased_by == 0 {
-                    save_to_location = ValueLocation::Immediate;
+                let other_result = cg.new_label();
+                if op == "<" || op == ">" {
+                    cg.write_instruction(Instruction::Bnc(other_result.clone()));
+                    push_bool_result(cg, other_result, 1);
                 } else {
-                    save_to_location = ValueLocation::SpMinus(stack_increased_by);
+                    cg.write_instruction(Instruction::Bca(other_result.clone()));
+                    push_bool_result(cg, other_result, 1);
+                }
+            }
+
+            TypedExpr::Shift(final_type, value, op, amount) => {
+                let size = final_type.size(&cg.defined_types);
+
+                //a runtime amount waits in Y while the value is evaluated
+                if !amount.value_known_at_compile() {
+                    amount.generate_onto_stack(cg);
+                }
+
+                let v = create_value(cg, value);
+                v.ld_to_scratch(SCRATCH_X, cg);
+                clean_after_value(cg, v);
+
+                if !amount.value_known_at_compile() {
+                    cg.pop_to_scratch(SCRATCH_Y, amount.final_type().size(&cg.defined_types));
                 }
 
+                shift_scratch_x(cg, final_type, op, amount);
+                cg.push_scratch(SCRATCH_X, size);
+            }
+
+            /*
+                condition
+                bze else
+                true value
+                jmp end
+            else:
+                false value
+            end:
+            */
+            TypedExpr::If(_, branches) => {
+                let (condition, t_value, f_value) = &**branches;
+                let else_label = cg.new_label();
+                let end = cg.new_label();
+
+                condition.generate_onto_stack(cg);
+                cg.pop_condition();
+                cg.write_instruction(Instruction::Bze(else_label.clone()));
+
+                t_value.generate_onto_stack(cg);
+                cg.write_instruction(Instruction::Jmp(end.clone()));
+
+                cg.write_label(else_label);
+                f_value.generate_onto_stack(cg);
+
+                cg.write_label(end);
+            }
+
+            //every break pushes the value before jumping to the end
+            TypedExpr::Loop(_, body) => cg.generate_loop(body),
+
+            TypedExpr::Unary(final_type, op, value) => {
+                let size = final_type.size(&cg.defined_types);
+
+                let v = create_value(cg, value);
 
                 match op.as_str() {
-                    "+" => {
-                        /*
-                        first byte:
-                            left.ld_nth_byte_to_reg(0, R0)
-                            right.ld_nth_byte_to_reg(0, R1)
-                            rmov R0
-                            add R1
-                            amov R0
-                            
-                            save_to_location R0
-
-                            if more than 1 byte:
-                                zac
-                                amov R0
-                                adc R0
-                                amov R0
-
-                            if location is immediate:
-                                increase_sp_by(1)
-                        
-                            if more than 1 byte:
-                                push R0
-
-
-                        for each byte after first:
-                            left.ld_nth_byte_to_reg(n, R0)
-                            right.ld_nth_byte_to_reg(n, R1)
-                            rmov R0
-                            add R1
-
-                            amov R1 ;stores result
-                            zac
-                            amov R0
-                            adc R0
-                            amov R0 ;stores carry result
-
-                            rmov R1
-                            pop R1 ;stores previous carry
-                            add R1
-                            amov R1 ;stores final result
-                            push R1
-
-                            zac
-                            adc R0
-                            amov R0
-                            
-                            increase_sp_by(1)
-                            push R0
-                        */
+                    //0 - value
+                    "-" => {
+                        v.ld_to_scratch(SCRATCH_Y, cg);
+                        clean_after_value(cg, v);
 
-                        left_value.ld_nth_byte_to_reg(R0, 0, cg);
-                        right_value.ld_nth_byte_to_reg(R1, 0, cg);
-                        cg.write_instruction(Instruction::Rmov(R0));
-                        cg.write_instruction(Instruction::Add(R1));
-                        cg.write_instruction(Instruction::Amov(R0));
-                        cg.write_instruction(Instruction::Push(R0));
-
-                        if final_type.size(&cg.defined_types) > 1 {
-                            cg.write_instruction(Instruction::Zac);
-                            cg.write_instruction(Instruction::Amov(R0));
-                            cg.write_instruction(Instruction::Adc(R0));
-                            cg.write_instruction(Instruction::Amov(R0));
-
-                            cg.increase_sp_by(1);
-                            cg.write_instruction(Instruction::Push(R0));
-
-                            for nth_byte in 1..final_type.size(&cg.defined_types) {
-                                left_value.ld_nth_byte_to_reg(R0, nth_byte, cg);
-                                right_value.ld_nth_byte_to_reg(R1, nth_byte, cg);
-
-                                cg.write_instruction(Instruction::Rmov(R0));
-                                cg.write_instruction(Instruction::Add(R1));
-
-                                cg.write_instruction(Instruction::Amov(R1));
-                                cg.write_instruction(Instruction::Zac);
-                                cg.write_instruction(Instruction::Amov(R0));
-                                cg.write_instruction(Instruction::Adc(R0));
-                                cg.write_instruction(Instruction::Amov(R0));
-
-                                cg.write_instruction(Instruction::Rmov(R1));
-                                cg.write_instruction(Instruction::Pop(R1));
-                                cg.write_instruction(Instruction::Add(R1));
-                                cg.write_instruction(Instruction::Amov(R1));
-                                cg.write_instruction(Instruction::Push(R1));
-
-                                cg.write_instruction(Instruction::Zac);
-                                cg.write_instruction(Instruction::Adc(R0));
-                                cg.write_instruction(Instruction::Amov(R0));
-
-                                cg.increase_sp_by(1);
-                                cg.write_instruction(Instruction::Push(R0));
-                            }
-                            
-                        } else {
-                            cg.increase_sp_by(1);
-                        }
+                        cg.set_scratch(SCRATCH_X, size, 0);
+                        cg.scratch_sub(SCRATCH_X, SCRATCH_Y, size);
+                        cg.push_scratch(SCRATCH_X, size);
                     }
 
-                    "-" => {
-                        todo!()
+                    "~" => {
+                        v.ld_to_scratch(SCRATCH_X, cg);
+                        clean_after_value(cg, v);
+
+                        cg.scratch_not(SCRATCH_X, size);
+                        cg.push_scratch(SCRATCH_X, size);
+                    }
+
+                    //1 if the value is 0, 0 otherwise
+                    "!" => {
+                        v.ld_to_scratch(SCRATCH_X, cg);
+                        clean_after_value(cg, v);
+
+                        cg.scratch_test_zero(SCRATCH_X, size);
+
+                        let not_zero = cg.new_label();
+                        cg.write_instruction(Instruction::Bnz(not_zero.clone()));
+                        push_bool_result(cg, not_zero, 1);
+
+                        for _ in 1..size {
+                            cg.push_imm(0);
+                        }
                     }
 
                     _ => unreachable!()
                 }
+            }
+
+            TypedExpr::Cast(to_type, value) => {
+                let from_type = value.final_type();
+                let from_size = from_type.size(&cg.defined_types);
+                let to_size = to_type.size(&cg.defined_types);
+
+                value.generate_onto_stack(cg);
+
+                if to_size < from_size {
+                    cg.drop_from_stack(from_size - to_size);
+                } else if to_size > from_size {
+                    /*
+                    imr R1 0
+                    ;if signed
+                    ld R0 last byte
+                    rmov R0
+                    bsi negative
+                    jmp extend
+                negative:
+                    imr R1 255
+                extend:
+                    push R1 for every new byte
+                    */
+
+                    cg.write_instruction(Instruction::Imr(R1, 0));
+
+                    if from_type.is_signed_type() {
+                        let negative = cg.new_label();
+                        let extend = cg.new_label();
+
+                        cg.ld_sp_minus_to_reg(R0, 1);
+                        cg.write_instruction(Instruction::Rmov(R0));
+                        cg.write_instruction(Instruction::Bsi(negative.clone()));
+                        cg.write_instruction(Instruction::Jmp(extend.clone()));
+                        cg.write_label(negative);
+                        cg.write_instruction(Instruction::Imr(R1, 255));
+                        cg.write_label(extend);
+                    }
+
+                    for _ in from_size..to_size {
+                        cg.push_reg(R1);
+                    }
+                }
+            }
+
+            TypedExpr::Assign(final_type, left, right) => {
+                let size = final_type.size(&cg.defined_types);
 
-                clean_after_two_values(cg, left_value, right_value);
+                right.generate_onto_stack(cg);
+                store_stack_top_to_lvalue(cg, left, size);
+            }
+
+            TypedExpr::CompoundAssign(final_type, left, op, right) => {
+                generate_compound_assign(cg, final_type, left, op, right);
+            }
+
+            TypedExpr::FnCall(ret_type, fn_name, args) => {
+                generate_fn_call(cg, ret_type, fn_name, args);
             }
 
             TypedExpr::Primary(final_type, primary) => {
                 match &**primary {
                     TypedPrimaryExpr::Grouping(body) => body.generate_onto_stack(cg),
 
-                    TypedPrimaryExpr::EnumVariant(_, variant) => {
-                        cg.write_instruction(Instruction::Imr(R0, variant.1));
-                        cg.write_instruction(Instruction::Push(R0));
-                        cg.increase_sp_by(1);
+                    //pushed as immediates above
+                    TypedPrimaryExpr::EnumVariant(..) | TypedPrimaryExpr::NumLiteral(_) => unreachable!(),
+
+                    //fields and items are laid out one after the other
+                    TypedPrimaryExpr::StructLiteral(_, fields) => {
+                        for (_, value) in fields {
+                            value.generate_onto_stack(cg);
+                        }
                     }
 
-                    TypedPrimaryExpr::NumLiteral(num) => {
-                        for nth in 0..num.get_type().size(&cg.defined_types) {
-                            let value = num.get_nth_byte(nth);
-                            cg.write_instruction(Instruction::Imr(R0, value));
-                            cg.write_instruction(Instruction::Push(R0));
-                            cg.increase_sp_by(1);
+                    TypedPrimaryExpr::ArrayLiteral(_, items) => {
+                        for item in items {
+                            item.generate_onto_stack(cg);
                         }
                     }
 
-                    TypedPrimaryExpr::Variable(var) => {
-                        if var.location_known_at_compile() {
-                            let (_, head_offset_from_bp) = cg.get_var(var.get_first_id());
-                            let offset_from_bp = head_offset_from_bp as i16 + var.get_total_offset();
+                    //every copy after the first is taken from the one below it
+                    TypedPrimaryExpr::ArrayRepeat(value, count) => {
+                        let size = value.final_type().size(&cg.defined_types);
+                        value.generate_onto_stack(cg);
 
-                            if offset_from_bp >= 0 {
+                        if *count == 0 {
+                            cg.drop_from_stack(size);
+                        }
 
-                            } else {
-                                //TODO: add negative pointer arithmetic
-                                todo!()
+                        for _ in 1..*count {
+                            for _ in 0..size {
+                                cg.ld_sp_minus_to_reg(R0, size);
+                                cg.push_reg(R0);
                             }
+                        }
+                    }
 
+                    TypedPrimaryExpr::StrLiteral(bytes) => {
+                        if let ValueType::Pointer(_) = final_type {
+                            let address = cg.data_address(bytes);
+                            cg.push_imm(address as u8);
+                            cg.push_imm((address >> 8) as u8);
                         } else {
+                            for byte in bytes {
+                                cg.push_imm(*byte);
+                            }
+                        }
+                    }
 
+                    TypedPrimaryExpr::Variable(var) => {
+                        let size = final_type.size(&cg.defined_types);
+
+                        if size == 0 {
+                            return
+                        }
+
+                        if var.location_known_at_compile() {
+                            let location = var.get_location(cg);
+
+                            for nth in 0..size {
+                                cg.set_mar_location_plus(location, nth);
+                                cg.write_instruction(Instruction::Ld(R0));
+                                cg.push_reg(R0);
+                            }
+                        } else {
+                            var.generate_address_onto_stack(cg, false);
+                            cg.deref_stack_address(size);
                         }
                     }
+
+                    TypedPrimaryExpr::Ref(_, var) => var.generate_address_onto_stack(cg, false),
+                }
+            }
+        }
+    }
+
+    ///Writes the value to `location`,
+    ///aggregate literals one scalar at a time instead of being pushed whole
+    pub fn generate_into_location(&self, cg: &mut CodeGenerator, location: ValueLocation) {
+        if let TypedExpr::Primary(_, primary) = self {
+            match &**primary {
+                TypedPrimaryExpr::StructLiteral(_, fields) => {
+                    for (offset, value) in fields {
+                        value.generate_into_location(cg, location.plus(*offset));
+                    }
+
+                    return
+                }
+
+                TypedPrimaryExpr::ArrayLiteral(item_type, items) => {
+                    let size = item_type.size(&cg.defined_types);
+
+                    for (n, item) in items.iter().enumerate() {
+                        item.generate_into_location(cg, location.plus(n as u16 * size));
+                    }
+
+                    return
+                }
+
+                TypedPrimaryExpr::ArrayRepeat(value, count) => {
+                    let size = value.final_type().size(&cg.defined_types);
+                    value.generate_onto_stack(cg);
+
+                    for n in 0..*count {
+                        cg.store_stack_top_to_location(location.plus(n * size), size);
+                    }
+
+                    cg.drop_from_stack(size);
+                    return
+                }
+
+                _ => {}
+            }
+        }
+
+        let size = self.final_type().size(&cg.defined_types);
+
+        self.generate_onto_stack(cg);
+        cg.store_stack_top_to_location(location, size);
+        cg.drop_from_stack(size);
+    }
+
+    ///Address of an assignable Expr is placed on the top of the stack
+    pub fn generate_address_onto_stack(&self, cg: &mut CodeGenerator) {
+        match self {
+            TypedExpr::Primary(_, primary) => {
+                match &**primary {
+                    TypedPrimaryExpr::Grouping(body) => body.generate_address_onto_stack(cg),
+
+                    TypedPrimaryExpr::Variable(var) => var.generate_address_onto_stack(cg, false),
+
+                    _ => unreachable!()
+                }
+            }
+
+            TypedExpr::Assign(final_type, left, _) => {
+                self.generate_onto_stack(cg);
+                cg.drop_from_stack(final_type.size(&cg.defined_types));
+                left.generate_address_onto_stack(cg);
+            }
+
+            _ => unreachable!()
+        }
+    }
+}
+
+impl TypedVariable {
+    ///if `has_parent`, the address of the struct this is a field of
+    ///is on top of the stack and gets replaced
+    pub fn generate_address_onto_stack(&self, cg: &mut CodeGenerator, has_parent: bool) {
+        match self {
+            TypedVariable::Id(_, name, field_offset) => {
+                if has_parent {
+                    cg.add_const_to_stack_u16(*field_offset);
+                    return
+                }
+
+                match cg.get_var(name.clone()).1 {
+                    ValueLocation::BpPlus(offset) => {
+                        /*
+                        ima offset[0:7]
+                        add BPL
+                        amov R0
+                        ima offset[8:15]
+                        adc BPH
+                        amov R1
+                        push R0
+                        push R1
+                        */
+
+                        cg.write_instruction(Instruction::Ima(offset as u8));
+                        cg.write_instruction(Instruction::Add(BPL));
+                        cg.write_instruction(Instruction::Amov(R0));
+                        cg.write_instruction(Instruction::Ima((offset >> 8) as u8));
+                        cg.write_instruction(Instruction::Adc(BPH));
+                        cg.write_instruction(Instruction::Amov(R1));
+                        cg.push_reg(R0);
+                        cg.push_reg(R1);
+                    }
+
+                    ValueLocation::Absolute(address) => {
+                        cg.push_imm(address as u8);
+                        cg.push_imm((address >> 8) as u8);
+                    }
+
+                    _ => unreachable!()
+                }
+            }
+
+            TypedVariable::StructField(_, body) => {
+                let (head, tail) = &**body;
+
+                head.generate_address_onto_stack(cg, has_parent);
+                tail.generate_address_onto_stack(cg, true);
+            }
+
+            TypedVariable::Array(item_type, head, index) => {
+                head.generate_address_onto_stack(cg, has_parent);
+
+                if let ValueType::Pointer(_) = head.final_type() {
+                    cg.deref_stack_address(2);
                 }
+
+                index.generate_onto_stack(cg);
+                cg.mul_stack_u16_by_const(item_type.size(&cg.defined_types));
+                cg.add_stack_u16s();
             }
+
+            //the address is the pointer's value
+            TypedVariable::Deref(_, pointer) => pointer.generate_onto_stack(cg),
+        }
+    }
+}
+
+///left operand ends up in SCRATCH_X, right in SCRATCH_Y
+fn generate_binary_to_scratch(cg: &mut CodeGenerator, binary_expr: &TypedBinaryExpr) {
+    let (left_value, right_value, _) = create_two_values(cg, &binary_expr.left, &binary_expr.right);
+
+    left_value.ld_to_scratch(SCRATCH_X, cg);
+    right_value.ld_to_scratch(SCRATCH_Y, cg);
+
+    clean_after_two_values(cg, left_value, right_value);
+}
+
+/*
+    push RESULT
+    jmp end
+other:
+    push !RESULT
+end:
+*/
+fn push_bool_result(cg: &mut CodeGenerator, other: LABEL, result: u8) {
+    let end = cg.new_label();
+
+    cg.push_imm(result);
+    cg.write_instruction(Instruction::Jmp(end.clone()));
+    cg.write_label(other);
+    cg.push_imm(1 - result);
+    cg.write_label(end);
+}
+
+///&& and || only evaluate the right side if needed
+fn generate_logical(cg: &mut CodeGenerator, binary_expr: &TypedBinaryExpr) {
+    let size = binary_expr.left.final_type().size(&cg.defined_types);
+    let is_and = binary_expr.operator == "&&";
+    let short_circuit = cg.new_label();
+
+    for side in [&binary_expr.left, &binary_expr.right] {
+        side.generate_onto_stack(cg);
+        cg.pop_to_scratch(SCRATCH_X, size);
+        cg.scratch_test_zero(SCRATCH_X, size);
+
+        if is_and {
+            cg.write_instruction(Instruction::Bze(short_circuit.clone()));
+        } else {
+            cg.write_instruction(Instruction::Bnz(short_circuit.clone()));
+        }
+    }
+
+    push_bool_result(cg, short_circuit, is_and as u8);
+}
+
+///X = X OP Y for the operators of Term and Factor, returns the scratch area holding the result
+fn scratch_operation(cg: &mut CodeGenerator, op: &str, final_type: &ValueType) -> u16 {
+    let size = final_type.size(&cg.defined_types);
+
+    match op {
+        "+" => cg.scratch_add(SCRATCH_X, SCRATCH_Y, size),
+        "-" => cg.scratch_sub(SCRATCH_X, SCRATCH_Y, size),
+        "&" => cg.scratch_and(SCRATCH_X, SCRATCH_Y, size),
+        "|" => cg.scratch_nor(SCRATCH_X, SCRATCH_Y, size, true),
+        "~|" => cg.scratch_nor(SCRATCH_X, SCRATCH_Y, size, false),
+        "^" => cg.scratch_xor(SCRATCH_X, SCRATCH_Y, size),
+
+        "*" => cg.call_runtime(RuntimeRoutine::Mul(size)),
+        "/" => cg.call_runtime(RuntimeRoutine::Div(size, final_type.is_signed_type())),
+
+        "%" => {
+            cg.call_runtime(RuntimeRoutine::Div(size, final_type.is_signed_type()));
+            return SCRATCH_Z
+        }
+
+        _ => unreachable!()
+    }
+
+    SCRATCH_X
+}
+
+///shifts X by `amount`, which is already in Y unless it is known at compile time
+fn shift_scratch_x(cg: &mut CodeGenerator, final_type: &ValueType, op: &str, amount: &TypedExpr) {
+    let size = final_type.size(&cg.defined_types);
+    let left = op == "<<";
+    let arithmetic = final_type.is_signed_type();
+
+    if !amount.value_known_at_compile() {
+        let amount_size = amount.final_type().size(&cg.defined_types);
+        cg.scratch_shift_by(SCRATCH_X, size, SCRATCH_Y, amount_size, left, arithmetic);
+        return
+    }
+
+    let count = amount.const_value().unwrap().shift_count((size * 8) as u8);
+    cg.scratch_shift(SCRATCH_X, size, count, left, arithmetic);
+}
+
+/*
+the lvalue is only evaluated once, its address is kept in SCRATCH_P
+unless its location is known at compile time
+
+    address of LEFT
+    RIGHT to Y
+    *address to X
+    X = X OP Y
+    *address = X
+    push X
+*/
+fn generate_compound_assign(cg: &mut CodeGenerator, final_type: &ValueType, left: &TypedExpr, op: &str, right: &TypedExpr) {
+    let size = final_type.size(&cg.defined_types);
+    let is_shift = op == "<<" || op == ">>";
+
+    let location = match left.location_known_at_compile() {
+        true => Some(left.get_location(cg)),
+        false => {
+            left.generate_address_onto_stack(cg);
+            None
         }
+    };
+
+    if !is_shift {
+        right.generate_onto_stack(cg);
+        cg.pop_to_scratch(SCRATCH_Y, size);
+    } else if !right.value_known_at_compile() {
+        right.generate_onto_stack(cg);
+        cg.pop_to_scratch(SCRATCH_Y, right.final_type().size(&cg.defined_types));
     }
-}
\ No newline at end of file
+
+    if location.is_none() {
+        cg.pop_to_scratch(SCRATCH_P, 2);
+    }
+
+    for nth in 0..size {
+        match location {
+            Some(t) => cg.set_mar_location_plus(t, nth),
+            None => cg.set_mar_ptr_plus(SCRATCH_P, nth),
+        }
+
+        cg.write_instruction(Instruction::Ld(R0));
+        cg.str_reg_to_abs(R0, SCRATCH_X + nth);
+    }
+
+    let result = match is_shift {
+        true => {
+            shift_scratch_x(cg, final_type, op, right);
+            SCRATCH_X
+        }
+
+        false => scratch_operation(cg, op, final_type),
+    };
+
+    for nth in 0..size {
+        cg.ld_abs_to_reg(R0, result + nth);
+
+        match location {
+            Some(t) => cg.set_mar_location_plus(t, nth),
+            None => cg.set_mar_ptr_plus(SCRATCH_P, nth),
+        }
+
+        cg.write_instruction(Instruction::Str(R0));
+    }
+
+    cg.push_scratch(result, size);
+}
+
+///stores the `size` bytes on top of the stack into an assignable Expr, leaving them on the stack
+fn store_stack_top_to_lvalue(cg: &mut CodeGenerator, lvalue: &TypedExpr, size: u16) {
+    if lvalue.location_known_at_compile() {
+        let location = lvalue.get_location(cg);
+        cg.store_stack_top_to_location(location, size);
+        return
+    }
+
+    lvalue.generate_address_onto_stack(cg);
+    cg.pop_to_scratch(SCRATCH_P, 2);
+
+    for nth in 0..size {
+        cg.ld_sp_minus_to_reg(R0, size - nth);
+        cg.set_mar_ptr_plus(SCRATCH_P, nth);
+        cg.write_instruction(Instruction::Str(R0));
+    }
+}
+
+/*
+caller:
+    reserve the return value
+    push BPL, BPH, call site id
+    push every argument
+    BP = SP - size of the arguments
+    jmp fn_NAME
+return_label:
+    SP = BP - 4
+    BPL, BPH = saved values at SP
+*/
+fn generate_fn_call(cg: &mut CodeGenerator, ret_type: &ValueType, fn_name: &str, args: &TypedArgs) {
+    let ret_size = ret_type.size(&cg.defined_types);
+    let (call_id, return_label) = cg.new_call_site(&fn_label(fn_name));
+
+    cg.increase_sp_by(ret_size);
+    cg.push_reg(BPL);
+    cg.push_reg(BPH);
+    cg.push_imm(call_id as u8);
+    cg.push_imm((call_id >> 8) as u8);
+
+    let mut args_size: u16 = 0;
+    for arg in args.items.iter() {
+        arg.generate_onto_stack(cg);
+        args_size += arg.final_type().size(&cg.defined_types);
+    }
+
+    cg.write_instruction(Instruction::Imr(R0, args_size as u8));
+    cg.write_instruction(Instruction::Rmov(SPL));
+    cg.write_instruction(Instruction::Sub(R0));
+    cg.write_instruction(Instruction::Amov(BPL));
+    cg.write_instruction(Instruction::Imr(R0, (args_size >> 8) as u8));
+    cg.write_instruction(Instruction::Rmov(SPH));
+    cg.write_instruction(Instruction::Sbc(R0));
+    cg.write_instruction(Instruction::Amov(BPH));
+
+    cg.write_instruction(Instruction::Jmp(fn_label(fn_name)));
+    cg.write_label(return_label);
+
+    cg.write_instruction(Instruction::Imr(R0, SAVED_FRAME_SIZE as u8));
+    cg.write_instruction(Instruction::Rmov(BPL));
+    cg.write_instruction(Instruction::Sub(R0));
+    cg.write_instruction(Instruction::Amov(SPL));
+    cg.write_instruction(Instruction::Imr(R0, 0));
+    cg.write_instruction(Instruction::Rmov(BPH));
+    cg.write_instruction(Instruction::Sbc(R0));
+    cg.write_instruction(Instruction::Amov(SPH));
+
+    cg.write_instruction(Instruction::Rmov(SPL));
+    cg.write_instruction(Instruction::Amov(MARL));
+    cg.write_instruction(Instruction::Rmov(SPH));
+    cg.write_instruction(Instruction::Amov(MARH));
+    cg.write_instruction(Instruction::Ld(R0));
+
+    cg.write_instruction(Instruction::Ima(1));
+    cg.write_instruction(Instruction::Add(MARL));
+    cg.write_instruction(Instruction::Amov(MARL));
+    cg.write_instruction(Instruction::Zac);
+    cg.write_instruction(Instruction::Adc(MARH));
+    cg.write_instruction(Instruction::Amov(MARH));
+    cg.write_instruction(Instruction::Ld(R1));
+
+    cg.write_instruction(Instruction::Rmov(R0));
+    cg.write_instruction(Instruction::Amov(BPL));
+    cg.write_instruction(Instruction::Rmov(R1));
+    cg.write_instruction(Instruction::Amov(BPH));
+}
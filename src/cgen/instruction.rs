@@ -12,12 +12,22 @@ pub const BPL_ADDRESS: u16 = 0xffff;
 pub type REG = u8;
 pub type LABEL = String;
 
+///indexed by REG
+pub const REG_NAMES: [&str; 8] = ["R0", "R1", "BPL", "BPH", "SPL", "SPH", "MARL", "MARH"];
+
+pub fn reg_name(reg: REG) -> &'static str {
+    REG_NAMES[reg as usize]
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub enum AssemblyCommand {
     Label(String),
     Comment(String),
     Instruction(Instruction),
+    Byte(u8), //raw data, bytes the disassembler can't decode
 }
 
+#[derive(Debug, Clone, PartialEq)]
 pub enum Instruction {
     Add(REG),
     Sub(REG),
@@ -39,7 +49,8 @@ pub enum Instruction {
     Bsi(LABEL),
     Bpa(LABEL),
     Zac,
-    Ima(REG), //REG,
+    ///loads an immediate into the accumulator
+    Ima(u8), //VALUE
     Imr(REG, u8), //REG, VALUE
     Spc,
     Str(REG),
@@ -50,4 +61,74 @@ pub enum Instruction {
     Hlt,
     Push(REG),
     Pop(REG),
-}
\ No newline at end of file
+}
+
+impl Instruction {
+    pub fn mnemonic(&self) -> &'static str {
+        match self {
+            Self::Add(_) => "add",
+            Self::Sub(_) => "sub",
+            Self::Adc(_) => "adc",
+            Self::Sbc(_) => "sbc",
+            Self::Ror(_) => "ror",
+            Self::Nor(_) => "nor",
+            Self::And(_) => "and",
+
+            Self::Stc => "stc",
+            Self::Clc => "clc",
+            Self::Rmov(_) => "rmov",
+            Self::Amov(_) => "amov",
+            Self::Jmp(_) => "jmp",
+            Self::Bca(_) => "bca",
+            Self::Bnc(_) => "bnc",
+            Self::Bze(_) => "bze",
+            Self::Bnz(_) => "bnz",
+            Self::Bsi(_) => "bsi",
+            Self::Bpa(_) => "bpa",
+            Self::Zac => "zac",
+            Self::Ima(_) => "ima",
+            Self::Imr(_, _) => "imr",
+            Self::Spc => "spc",
+            Self::Str(_) => "str",
+            Self::Ld(_) => "ld",
+            Self::In(_) => "in",
+            Self::Out(_) => "out",
+            Self::Nop => "nop",
+            Self::Hlt => "hlt",
+            Self::Push(_) => "push",
+            Self::Pop(_) => "pop",
+        }
+    }
+}
+
+///Textual assembly, one command per line
+pub fn write_asm(commands: &[AssemblyCommand]) -> String {
+    let mut out = String::new();
+
+    for c in commands {
+        out.push_str(&c.to_string());
+        out.push('\n');
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_asm() {
+        let commands = vec![
+            AssemblyCommand::Comment("start".to_string()),
+            AssemblyCommand::Label("loop".to_string()),
+            AssemblyCommand::Instruction(Instruction::Imr(R0, 5)),
+            AssemblyCommand::Instruction(Instruction::Ld(MARL)),
+            AssemblyCommand::Instruction(Instruction::Ima(255)),
+            AssemblyCommand::Instruction(Instruction::Bnz("loop".to_string())),
+            AssemblyCommand::Instruction(Instruction::Hlt),
+        ];
+
+        assert_eq!(write_asm(&commands), "; start\nloop:\n    imr R0 5\n    ld MARL\n    ima 255\n    bnz loop\n    hlt\n");
+    }
+}
@@ -0,0 +1,324 @@
+//! Routines for the operations the ISA has no instruction for.
+//!
+//! A routine is emitted once for every width it is used with, operands are
+//! passed in `SCRATCH_X` and `SCRATCH_Y` and the call site id in the first
+//! two bytes of `RUNTIME_STATE`. Calls return through a dispatch like the
+//! one of functions.
+//!
+//! - `rt_mul_uN`: X = X * Y, truncated to the width. The truncated product
+//!   has the same bits for signed operands, so it serves `iN` as well.
+//! - `rt_div_uN`, `rt_div_iN`: X = X / Y and Z = X % Y. Signed division
+//!   truncates towards zero and the remainder takes the sign of X.
+//!   Dividing by zero gives a quotient with every bit set and leaves X as
+//!   the remainder, before the signs are applied.
+
+use super::*;
+
+const COUNTER: u16 = RUNTIME_STATE + 2;
+const QUOTIENT_SIGN: u16 = RUNTIME_STATE + 3;
+const REMAINDER_SIGN: u16 = RUNTIME_STATE + 4;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RuntimeRoutine {
+    Mul(u16), //SIZE
+    Div(u16, bool), //(SIZE, SIGNED)
+}
+
+impl RuntimeRoutine {
+    pub fn label(&self) -> String {
+        match self {
+            Self::Mul(size) => format!("rt_mul_u{}", size * 8),
+            Self::Div(size, false) => format!("rt_div_u{}", size * 8),
+            Self::Div(size, true) => format!("rt_div_i{}", size * 8),
+        }
+    }
+}
+
+impl CodeGenerator {
+    /*
+    RUNTIME_STATE[0:1] = call site id
+    jmp rt_ROUTINE
+    return_label:
+    */
+    pub fn call_runtime(&mut self, routine: RuntimeRoutine) {
+        if !self.runtime_routines.contains(&routine) {
+            self.runtime_routines.push(routine);
+        }
+
+        let (call_id, return_label) = self.new_call_site(&routine.label());
+
+        self.write_instruction(Instruction::Imr(R0, call_id as u8));
+        self.str_reg_to_abs(R0, RUNTIME_STATE);
+        self.write_instruction(Instruction::Imr(R0, (call_id >> 8) as u8));
+        self.str_reg_to_abs(R0, RUNTIME_STATE + 1);
+
+        self.write_instruction(Instruction::Jmp(routine.label()));
+        self.write_label(return_label);
+    }
+
+    pub fn generate_runtime_routine(&mut self, routine: RuntimeRoutine) {
+        self.write_label(routine.label());
+
+        match routine {
+            RuntimeRoutine::Mul(size) => self.generate_mul(size),
+            RuntimeRoutine::Div(size, false) => self.generate_unsigned_div(size),
+            RuntimeRoutine::Div(size, true) => self.generate_signed_div(size),
+        }
+
+        self.ld_abs_to_reg(R0, RUNTIME_STATE);
+        self.ld_abs_to_reg(R1, RUNTIME_STATE + 1);
+        self.write_call_site_dispatch(&routine.label());
+    }
+
+    /*
+    Z = 0
+    for every bit of X from the top:
+        Z <<= 1
+        X <<= 1
+        bnc skip
+        Z += Y
+    skip:
+    X = Z
+    */
+    fn generate_mul(&mut self, size: u16) {
+        self.set_scratch(SCRATCH_Z, size, 0);
+
+        let loop_label = self.start_counted_loop(size * 8);
+        let skip = self.new_label();
+
+        self.scratch_shift_once(SCRATCH_Z, size, true, false);
+        self.scratch_shift_once(SCRATCH_X, size, true, false);
+        self.write_instruction(Instruction::Bnc(skip.clone()));
+        self.scratch_add(SCRATCH_Z, SCRATCH_Y, size);
+        self.write_label(skip);
+
+        self.end_counted_loop(loop_label);
+
+        self.scratch_copy(SCRATCH_X, SCRATCH_Z, size);
+    }
+
+    /*
+    restoring division, the quotient is shifted into X as X is shifted out
+
+    Z = 0
+    for every bit of X from the top:
+        X <<= 1
+        Z = Z << 1 | carry
+        bca subtract    ;Z got bigger than any Y
+        Z -= Y
+        bnc fits
+        Z += Y
+        jmp next
+    subtract:
+        Z -= Y
+    fits:
+        X |= 1
+    next:
+    */
+    fn generate_unsigned_div(&mut self, size: u16) {
+        self.set_scratch(SCRATCH_Z, size, 0);
+
+        let loop_label = self.start_counted_loop(size * 8);
+        let subtract = self.new_label();
+        let fits = self.new_label();
+        let next = self.new_label();
+
+        self.scratch_shift_once(SCRATCH_X, size, true, false);
+        self.scratch_rotate_left(SCRATCH_Z, size);
+        self.write_instruction(Instruction::Bca(subtract.clone()));
+
+        self.scratch_sub(SCRATCH_Z, SCRATCH_Y, size);
+        self.write_instruction(Instruction::Bnc(fits.clone()));
+        self.scratch_add(SCRATCH_Z, SCRATCH_Y, size);
+        self.write_instruction(Instruction::Jmp(next.clone()));
+
+        self.write_label(subtract);
+        self.scratch_sub(SCRATCH_Z, SCRATCH_Y, size);
+
+        self.write_label(fits);
+        self.ld_abs_to_reg(R0, SCRATCH_X);
+        self.write_instruction(Instruction::Ima(1));
+        self.write_instruction(Instruction::Nor(R0));
+        self.write_instruction(Instruction::Amov(R0));
+        self.write_instruction(Instruction::Nor(R0));
+        self.write_instruction(Instruction::Amov(R0));
+        self.str_reg_to_abs(R0, SCRATCH_X);
+
+        self.write_label(next);
+        self.end_counted_loop(loop_label);
+    }
+
+    /*
+    divides the magnitudes, then applies the signs
+
+    QUOTIENT_SIGN = REMAINDER_SIGN = X < 0
+    X = |X|
+    QUOTIENT_SIGN ^= Y < 0
+    Y = |Y|
+    unsigned division
+    X = -X if QUOTIENT_SIGN
+    Z = -Z if REMAINDER_SIGN
+    */
+    fn generate_signed_div(&mut self, size: u16) {
+        self.write_instruction(Instruction::Imr(R0, 0));
+        self.str_reg_to_abs(R0, QUOTIENT_SIGN);
+        self.str_reg_to_abs(R0, REMAINDER_SIGN);
+
+        self.abs_toggling_flags(SCRATCH_X, size, &[QUOTIENT_SIGN, REMAINDER_SIGN]);
+        self.abs_toggling_flags(SCRATCH_Y, size, &[QUOTIENT_SIGN]);
+
+        self.generate_unsigned_div(size);
+
+        self.negate_if_flag(SCRATCH_X, size, QUOTIENT_SIGN);
+        self.negate_if_flag(SCRATCH_Z, size, REMAINDER_SIGN);
+    }
+
+    /*
+    ld R0 scratch[size - 1]
+    rmov R0
+    bsi negative
+    jmp done
+    negative:
+        scratch = -scratch
+        every flag = 1 - flag
+    done:
+    */
+    fn abs_toggling_flags(&mut self, scratch: u16, size: u16, flags: &[u16]) {
+        let negative = self.new_label();
+        let done = self.new_label();
+
+        self.ld_abs_to_reg(R0, scratch + size - 1);
+        self.write_instruction(Instruction::Rmov(R0));
+        self.write_instruction(Instruction::Bsi(negative.clone()));
+        self.write_instruction(Instruction::Jmp(done.clone()));
+
+        self.write_label(negative);
+        self.scratch_negate(scratch, size);
+
+        for flag in flags {
+            self.ld_abs_to_reg(R0, *flag);
+            self.write_instruction(Instruction::Imr(R1, 1));
+            self.write_instruction(Instruction::Rmov(R1));
+            self.write_instruction(Instruction::Sub(R0));
+            self.write_instruction(Instruction::Amov(R0));
+            self.str_reg_to_abs(R0, *flag);
+        }
+
+        self.write_label(done);
+    }
+
+    fn negate_if_flag(&mut self, scratch: u16, size: u16, flag: u16) {
+        let done = self.new_label();
+
+        self.ld_abs_to_reg(R0, flag);
+        self.write_instruction(Instruction::Rmov(R0));
+        self.write_instruction(Instruction::Bze(done.clone()));
+        self.scratch_negate(scratch, size);
+        self.write_label(done);
+    }
+
+    ///`count` is at most 255, returns the label to loop back to
+    fn start_counted_loop(&mut self, count: u16) -> String {
+        let loop_label = self.new_label();
+
+        self.write_instruction(Instruction::Imr(R0, count as u8));
+        self.str_reg_to_abs(R0, COUNTER);
+        self.write_label(loop_label.clone());
+
+        loop_label
+    }
+
+    fn end_counted_loop(&mut self, loop_label: String) {
+        /*
+        ld R0 COUNTER
+        imr R1 1
+        rmov R0
+        sub R1
+        amov R0
+        str R0 COUNTER
+        bnz loop
+        */
+
+        self.ld_abs_to_reg(R0, COUNTER);
+        self.write_instruction(Instruction::Imr(R1, 1));
+        self.write_instruction(Instruction::Rmov(R0));
+        self.write_instruction(Instruction::Sub(R1));
+        self.write_instruction(Instruction::Amov(R0));
+        self.str_reg_to_abs(R0, COUNTER);
+        self.write_instruction(Instruction::Bnz(loop_label));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::emulator::{Emulator, DEFAULT_MAX_STEPS};
+    use crate::{compile, CompileOptions, Emit};
+
+    ///the top level variables after running `source`
+    fn run(source: &str) -> String {
+        let artifacts = compile(source, &CompileOptions { emit: Emit::Bin }).unwrap();
+        let mut emu = Emulator::new(artifacts.bin.clone().unwrap());
+        emu.run(DEFAULT_MAX_STEPS).unwrap();
+
+        artifacts.format_globals(&emu)
+    }
+
+    ///checks `x * y`, `x / y` and `x % y` of every pair against i128 arithmetic
+    ///wrapped to the width, the operands are variables so nothing gets folded
+    fn check_routines(ty: &str, pairs: &[(i128, i128)]) {
+        let bits: u32 = ty[1..].parse().unwrap();
+        let signed = ty.starts_with('i');
+
+        let wrap = |value: i128| {
+            let shift = 128 - bits;
+
+            match signed {
+                true => (value << shift) >> shift,
+                false => (((value << shift) as u128) >> shift) as i128,
+            }
+        };
+
+        let mut source = String::new();
+        let mut expected = String::new();
+
+        for (n, (x, y)) in pairs.iter().enumerate() {
+            source.push_str(&format!("let x{n}: {ty} = {x}{ty};\nlet y{n}: {ty} = {y}{ty};\n"));
+            source.push_str(&format!("let p{n}: {ty} = x{n} * y{n};\nlet q{n}: {ty} = x{n} / y{n};\nlet r{n}: {ty} = x{n} % y{n};\n"));
+
+            let (q, r) = match *y {
+                0 if signed && *x < 0 => (1, *x),
+                0 => (-1, *x),
+                _ => (x / y, x % y),
+            };
+
+            expected.push_str(&format!("x{n}: {ty} = {x}\ny{n}: {ty} = {y}\n"));
+            expected.push_str(&format!("p{n}: {ty} = {}\nq{n}: {ty} = {}\nr{n}: {ty} = {}\n",
+                wrap(x.wrapping_mul(*y)), wrap(q), wrap(r)));
+        }
+
+        assert_eq!(run(&source), expected, "{}", ty);
+    }
+
+    #[test]
+    fn test_unsigned_routines() {
+        for ty in ["u8", "u16", "u32", "u64"] {
+            let max = (1i128 << ty[1..].parse::<u32>().unwrap()) - 1;
+
+            check_routines(ty, &[(max, max), (max, 7), (max, 1), (6, 7), (200, 13), (5, 0)]);
+        }
+    }
+
+    #[test]
+    fn test_signed_routines() {
+        for ty in ["i8", "i16", "i32", "i64"] {
+            let min = -(1i128 << (ty[1..].parse::<u32>().unwrap() - 1));
+            let max = -min - 1;
+
+            check_routines(ty, &[
+                (min, -1), (min, 1), (min, 2), (max, -1), (max, max),
+                (-7, 2), (7, -2), (-7, -2), (-6, 3),
+                (5, 0), (-5, 0),
+            ]);
+        }
+    }
+}
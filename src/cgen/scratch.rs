@@ -0,0 +1,407 @@
+use super::*;
+
+//operations on the scratch areas, every byte is addressed with imr
+//so the carry flag survives from one byte to the next
+
+impl CodeGenerator {
+    ///moves the `size` bytes on top of the stack to a scratch area
+    pub fn pop_to_scratch(&mut self, scratch: u16, size: u16) {
+        for nth in 0..size {
+            self.ld_sp_minus_to_reg(R0, size - nth);
+            self.str_reg_to_abs(R0, scratch + nth);
+        }
+
+        self.drop_from_stack(size);
+    }
+
+    pub fn push_scratch(&mut self, scratch: u16, size: u16) {
+        for nth in 0..size {
+            self.ld_abs_to_reg(R0, scratch + nth);
+            self.push_reg(R0);
+        }
+    }
+
+    ///overwrites R0
+    pub fn set_scratch(&mut self, scratch: u16, size: u16, value: u64) {
+        for nth in 0..size {
+            self.write_instruction(Instruction::Imr(R0, (value >> (nth * 8)) as u8));
+            self.str_reg_to_abs(R0, scratch + nth);
+        }
+    }
+
+    /*
+    clc
+    for every byte:
+        ld R0 dst[n]
+        ld R1 src[n]
+        rmov R0
+        OP R1 ;adc or sbc
+        amov R0
+        str R0 dst[n]
+    */
+    pub fn scratch_carry_chain(&mut self, dst: u16, src: u16, size: u16, op: fn(REG) -> Instruction) {
+        self.write_instruction(Instruction::Clc);
+
+        for nth in 0..size {
+            self.ld_abs_to_reg(R0, dst + nth);
+            self.ld_abs_to_reg(R1, src + nth);
+            self.write_instruction(Instruction::Rmov(R0));
+            self.write_instruction(op(R1));
+            self.write_instruction(Instruction::Amov(R0));
+            self.str_reg_to_abs(R0, dst + nth);
+        }
+    }
+
+    ///dst += src
+    pub fn scratch_add(&mut self, dst: u16, src: u16, size: u16) {
+        self.scratch_carry_chain(dst, src, size, Instruction::Adc);
+    }
+
+    ///dst -= src, the carry is set if src was bigger
+    pub fn scratch_sub(&mut self, dst: u16, src: u16, size: u16) {
+        self.scratch_carry_chain(dst, src, size, Instruction::Sbc);
+    }
+
+    ///dst = dst & src
+    pub fn scratch_and(&mut self, dst: u16, src: u16, size: u16) {
+        for nth in 0..size {
+            self.ld_abs_to_reg(R0, dst + nth);
+            self.ld_abs_to_reg(R1, src + nth);
+            self.write_instruction(Instruction::Rmov(R0));
+            self.write_instruction(Instruction::And(R1));
+            self.write_instruction(Instruction::Amov(R0));
+            self.str_reg_to_abs(R0, dst + nth);
+        }
+    }
+
+    ///dst = dst ~| src, or dst | src if `negate` is set
+    pub fn scratch_nor(&mut self, dst: u16, src: u16, size: u16, negate: bool) {
+        for nth in 0..size {
+            self.ld_abs_to_reg(R0, dst + nth);
+            self.ld_abs_to_reg(R1, src + nth);
+            self.write_instruction(Instruction::Rmov(R0));
+            self.write_instruction(Instruction::Nor(R1));
+            self.write_instruction(Instruction::Amov(R0));
+
+            if negate {
+                self.write_instruction(Instruction::Nor(R0));
+                self.write_instruction(Instruction::Amov(R0));
+            }
+
+            self.str_reg_to_abs(R0, dst + nth);
+        }
+    }
+
+    ///dst = dst ^ src
+    pub fn scratch_xor(&mut self, dst: u16, src: u16, size: u16) {
+        /*
+        for every byte:
+            ld R0 dst[n]
+            ld R1 src[n]
+            rmov R0
+            and R1
+            amov MARL   ;MAR is free until the store
+            rmov R0
+            nor R1
+            nor MARL    ;A = ~(~(x | y) | (x & y)) = x ^ y
+            amov R0
+            str R0 dst[n]
+        */
+
+        for nth in 0..size {
+            self.ld_abs_to_reg(R0, dst + nth);
+            self.ld_abs_to_reg(R1, src + nth);
+            self.write_instruction(Instruction::Rmov(R0));
+            self.write_instruction(Instruction::And(R1));
+            self.write_instruction(Instruction::Amov(MARL));
+            self.write_instruction(Instruction::Rmov(R0));
+            self.write_instruction(Instruction::Nor(R1));
+            self.write_instruction(Instruction::Nor(MARL));
+            self.write_instruction(Instruction::Amov(R0));
+            self.str_reg_to_abs(R0, dst + nth);
+        }
+    }
+
+    ///scratch = ~scratch
+    pub fn scratch_not(&mut self, scratch: u16, size: u16) {
+        for nth in 0..size {
+            self.ld_abs_to_reg(R0, scratch + nth);
+            self.write_instruction(Instruction::Rmov(R0));
+            self.write_instruction(Instruction::Nor(R0));
+            self.write_instruction(Instruction::Amov(R0));
+            self.str_reg_to_abs(R0, scratch + nth);
+        }
+    }
+
+    ///scratch = -scratch
+    pub fn scratch_negate(&mut self, scratch: u16, size: u16) {
+        /*
+        stc
+        for every byte:
+            ld R0 scratch[n]
+            rmov R0
+            nor R0
+            amov R0
+            zac
+            adc R0  ;A = ~byte + carry
+            amov R0
+            str R0 scratch[n]
+        */
+
+        self.write_instruction(Instruction::Stc);
+
+        for nth in 0..size {
+            self.ld_abs_to_reg(R0, scratch + nth);
+            self.write_instruction(Instruction::Rmov(R0));
+            self.write_instruction(Instruction::Nor(R0));
+            self.write_instruction(Instruction::Amov(R0));
+            self.write_instruction(Instruction::Zac);
+            self.write_instruction(Instruction::Adc(R0));
+            self.write_instruction(Instruction::Amov(R0));
+            self.str_reg_to_abs(R0, scratch + nth);
+        }
+    }
+
+    pub fn scratch_copy(&mut self, dst: u16, src: u16, size: u16) {
+        for nth in 0..size {
+            self.ld_abs_to_reg(R0, src + nth);
+            self.str_reg_to_abs(R0, dst + nth);
+        }
+    }
+
+    ///sets the zero flag if every byte is 0
+    pub fn scratch_test_zero(&mut self, scratch: u16, size: u16) {
+        /*
+        ld R0 scratch[0]
+        rmov R0
+
+        for every other byte:
+            ld R1 scratch[n]
+            nor R1
+            amov R0
+            nor R0  ;A = A | R1
+        */
+
+        self.ld_abs_to_reg(R0, scratch);
+        self.write_instruction(Instruction::Rmov(R0));
+
+        for nth in 1..size {
+            self.ld_abs_to_reg(R1, scratch + nth);
+            self.write_instruction(Instruction::Nor(R1));
+            self.write_instruction(Instruction::Amov(R0));
+            self.write_instruction(Instruction::Nor(R0));
+        }
+    }
+
+    ///sets the carry if x < y
+    pub fn scratch_less_than(&mut self, x: u16, y: u16, size: u16, signed: bool) {
+        //flipping the sign bits turns a signed comparison into an unsigned one
+        if signed {
+            for scratch in [x, y] {
+                self.ld_abs_to_reg(R0, scratch + size - 1);
+                self.write_instruction(Instruction::Ima(0x80));
+                self.write_instruction(Instruction::Add(R0));
+                self.write_instruction(Instruction::Amov(R0));
+                self.str_reg_to_abs(R0, scratch + size - 1);
+            }
+        }
+
+        self.write_instruction(Instruction::Clc);
+
+        for nth in 0..size {
+            self.ld_abs_to_reg(R0, x + nth);
+            self.ld_abs_to_reg(R1, y + nth);
+            self.write_instruction(Instruction::Rmov(R0));
+            self.write_instruction(Instruction::Sbc(R1));
+        }
+    }
+
+    ///jumps to `not_equal` if x != y
+    pub fn scratch_equal(&mut self, x: u16, y: u16, size: u16, not_equal: &LABEL) {
+        for nth in 0..size {
+            self.ld_abs_to_reg(R0, x + nth);
+            self.ld_abs_to_reg(R1, y + nth);
+            self.write_instruction(Instruction::Rmov(R0));
+            self.write_instruction(Instruction::Sub(R1));
+            self.write_instruction(Instruction::Bnz(not_equal.clone()));
+        }
+    }
+
+    ///shifts by one bit, `arithmetic` keeps the sign of right shifts
+    pub fn scratch_shift_once(&mut self, scratch: u16, size: u16, left: bool, arithmetic: bool) {
+        if left {
+            /*
+            clc
+            for every byte:
+                rmov byte
+                adc byte
+            */
+
+            self.write_instruction(Instruction::Clc);
+            self.scratch_rotate_left(scratch, size);
+
+            return
+        }
+
+        /*
+        carry = sign bit or 0
+        for every byte from the last:
+            ror byte
+        */
+
+        if arithmetic {
+            self.ld_abs_to_reg(R0, scratch + size - 1);
+            self.write_instruction(Instruction::Rmov(R0));
+            self.write_instruction(Instruction::Add(R0));
+        } else {
+            self.write_instruction(Instruction::Clc);
+        }
+
+        for nth in (0..size).rev() {
+            self.ld_abs_to_reg(R0, scratch + nth);
+            self.write_instruction(Instruction::Ror(R0));
+            self.write_instruction(Instruction::Amov(R0));
+            self.write_instruction(Instruction::Str(R0));
+        }
+    }
+
+    ///shifts left by one bit with the carry going into bit 0,
+    ///the top bit ends up in the carry
+    pub fn scratch_rotate_left(&mut self, scratch: u16, size: u16) {
+        for nth in 0..size {
+            self.ld_abs_to_reg(R0, scratch + nth);
+            self.write_instruction(Instruction::Rmov(R0));
+            self.write_instruction(Instruction::Adc(R0));
+            self.write_instruction(Instruction::Amov(R0));
+            self.write_instruction(Instruction::Str(R0));
+        }
+    }
+
+    ///shifts by `amount`, a loop counted down in R1
+    pub fn scratch_shift(&mut self, scratch: u16, size: u16, amount: u8, left: bool, arithmetic: bool) {
+        if amount == 0 {
+            return
+        }
+
+        let loop_label = self.new_label();
+
+        self.write_instruction(Instruction::Imr(R1, amount));
+        self.write_label(loop_label.clone());
+
+        self.scratch_shift_once(scratch, size, left, arithmetic);
+
+        self.write_instruction(Instruction::Imr(R0, 1));
+        self.write_instruction(Instruction::Rmov(R1));
+        self.write_instruction(Instruction::Sub(R0));
+        self.write_instruction(Instruction::Amov(R1));
+        self.write_instruction(Instruction::Bnz(loop_label));
+    }
+
+    ///shifts by the `amount_size` byte value at `amount`, which is clamped to the bits of the value
+    pub fn scratch_shift_by(&mut self, scratch: u16, size: u16, amount: u16, amount_size: u16, left: bool, arithmetic: bool) {
+        /*
+        R1 = bits
+        for every byte of the amount above the first:
+            bnz clamped
+        R0 = first byte
+        if R0 <= bits: R1 = R0
+        clamped:
+        bze end
+        loop:
+            shift once
+            R1 = R1 - 1
+            bnz loop
+        end:
+        */
+
+        let bits = (size * 8) as u8;
+        let clamped = self.new_label();
+        let loop_label = self.new_label();
+        let end = self.new_label();
+
+        self.write_instruction(Instruction::Imr(R1, bits));
+
+        for nth in 1..amount_size {
+            self.ld_abs_to_reg(R0, amount + nth);
+            self.write_instruction(Instruction::Rmov(R0));
+            self.write_instruction(Instruction::Bnz(clamped.clone()));
+        }
+
+        self.ld_abs_to_reg(R0, amount);
+        self.write_instruction(Instruction::Rmov(R1));
+        self.write_instruction(Instruction::Sub(R0));
+        self.write_instruction(Instruction::Bca(clamped.clone()));
+        self.write_instruction(Instruction::Rmov(R0));
+        self.write_instruction(Instruction::Amov(R1));
+
+        self.write_label(clamped);
+        self.write_instruction(Instruction::Rmov(R1));
+        self.write_instruction(Instruction::Bze(end.clone()));
+
+        self.write_label(loop_label.clone());
+        self.scratch_shift_once(scratch, size, left, arithmetic);
+
+        self.write_instruction(Instruction::Imr(R0, 1));
+        self.write_instruction(Instruction::Rmov(R1));
+        self.write_instruction(Instruction::Sub(R0));
+        self.write_instruction(Instruction::Amov(R1));
+        self.write_instruction(Instruction::Bnz(loop_label));
+
+        self.write_label(end);
+    }
+
+    ///replaces the u16 on top of the stack with itself * n
+    pub fn mul_stack_u16_by_const(&mut self, n: u16) {
+        if n == 1 {
+            return
+        }
+
+        //result is built in Y, from the highest bit of n down
+        self.pop_to_scratch(SCRATCH_X, 2);
+        self.set_scratch(SCRATCH_Y, 2, 0);
+
+        let mut started = false;
+        for bit in (0..16).rev() {
+            if started {
+                self.scratch_add(SCRATCH_Y, SCRATCH_Y, 2);
+            }
+
+            if (n >> bit) & 1 == 1 {
+                self.scratch_add(SCRATCH_Y, SCRATCH_X, 2);
+                started = true;
+            }
+        }
+
+        self.push_scratch(SCRATCH_Y, 2);
+    }
+
+    ///replaces the two u16s on top of the stack with their sum
+    pub fn add_stack_u16s(&mut self) {
+        self.pop_to_scratch(SCRATCH_Y, 2);
+        self.pop_to_scratch(SCRATCH_X, 2);
+        self.scratch_add(SCRATCH_X, SCRATCH_Y, 2);
+        self.push_scratch(SCRATCH_X, 2);
+    }
+
+    pub fn add_const_to_stack_u16(&mut self, n: u16) {
+        if n == 0 {
+            return
+        }
+
+        self.pop_to_scratch(SCRATCH_X, 2);
+        self.set_scratch(SCRATCH_Y, 2, n as u64);
+        self.scratch_add(SCRATCH_X, SCRATCH_Y, 2);
+        self.push_scratch(SCRATCH_X, 2);
+    }
+
+    ///replaces the address on top of the stack with the `size` bytes it points to
+    pub fn deref_stack_address(&mut self, size: u16) {
+        self.pop_to_scratch(SCRATCH_P, 2);
+
+        for nth in 0..size {
+            self.set_mar_ptr_plus(SCRATCH_P, nth);
+            self.write_instruction(Instruction::Ld(R0));
+            self.push_reg(R0);
+        }
+    }
+}
@@ -1 +1,378 @@
-use super::*;
\ No newline at end of file
+use super::*;
+use crate::expr::NumLiteral;
+
+//names no program can declare, for the variables a for loop keeps to itself
+const FOR_END: &str = "for end";
+const FOR_INDEX: &str = "for index";
+const FOR_ARRAY: &str = "for array";
+
+impl TypedStmt {
+    pub fn generate(&self, cg: &mut CodeGenerator) {
+        match self {
+            //the initializer can't see the variable it initializes,
+            //it is written straight into the variable's space
+            TypedStmt::VarDeclr(name, var_type, init) => {
+                let location = cg.reserve_var_space(var_type);
+
+                if let Some(init) = init {
+                    init.generate_into_location(cg, location);
+                }
+
+                cg.declare_var_at(name.clone(), var_type.clone(), location);
+            }
+
+            TypedStmt::ExprStmt(expr) => {
+                expr.generate_onto_stack(cg);
+                cg.drop_from_stack(expr.final_type().size(&cg.defined_types));
+            }
+
+            TypedStmt::Block(stmts) => {
+                cg.enter_scope();
+
+                for stmt in stmts {
+                    stmt.generate(cg);
+                }
+
+                cg.leave_scope();
+            }
+
+            TypedStmt::LoopStmt(body) => cg.generate_loop(body),
+
+            /*
+            start:
+                condition
+                bze end
+                body
+                jmp start
+            end:
+            */
+            TypedStmt::WhileStmt(condition, body) => {
+                let start = cg.new_label();
+                let end = cg.new_label();
+
+                cg.write_label(start.clone());
+                condition.generate_onto_stack(cg);
+                cg.pop_condition();
+                cg.write_instruction(Instruction::Bze(end.clone()));
+
+                cg.loop_labels.push((start.clone(), end.clone()));
+                body.generate(cg);
+                cg.loop_labels.pop();
+
+                cg.write_instruction(Instruction::Jmp(start));
+                cg.write_label(end);
+            }
+
+            /*
+                VAR = START, END is evaluated once
+            start:
+                VAR < END, or VAR <= END if inclusive
+                bze end
+                body
+            step:
+                if inclusive: VAR == END, bnz end
+                VAR += 1
+                jmp start
+            end:
+            */
+            TypedStmt::ForRange(name, var_type, start_value, end_value, inclusive, body) => {
+                let size = var_type.size(&cg.defined_types);
+                let start = cg.new_label();
+                let step = cg.new_label();
+                let end = cg.new_label();
+
+                cg.enter_scope();
+
+                //the bounds can't see the loop variable
+                start_value.generate_onto_stack(cg);
+                TypedStmt::VarDeclr(FOR_END.to_string(), var_type.clone(), Some(end_value.clone())).generate(cg);
+
+                cg.declare_var(name.clone(), var_type.clone());
+                let (_, location) = cg.get_var(name.clone());
+                cg.store_stack_top_to_location(location, size);
+                cg.drop_from_stack(size);
+
+                let var = var_expr(name, var_type);
+                let last = var_expr(FOR_END, var_type);
+                let op = if *inclusive { "<=" } else { "<" };
+
+                cg.write_label(start.clone());
+                binary_expr(TypedExpr::Comparison, op, var.clone(), last.clone()).generate_onto_stack(cg);
+                cg.pop_condition();
+                cg.write_instruction(Instruction::Bze(end.clone()));
+
+                cg.loop_labels.push((step.clone(), end.clone()));
+                body.generate(cg);
+                cg.loop_labels.pop();
+
+                cg.write_label(step);
+
+                //stepping past END could wrap around
+                if *inclusive {
+                    binary_expr(TypedExpr::Equality, "==", var.clone(), last).generate_onto_stack(cg);
+                    cg.pop_condition();
+                    cg.write_instruction(Instruction::Bnz(end.clone()));
+                }
+
+                increment(var, var_type).generate(cg);
+                cg.write_instruction(Instruction::Jmp(start));
+                cg.write_label(end);
+
+                cg.leave_scope();
+            }
+
+            /*
+                for array = &ARRAY, for index = 0
+            start:
+                for index < LENGTH
+                bze end
+                VAR = for array[for index]
+                body
+            step:
+                for index += 1
+                jmp start
+            end:
+            */
+            TypedStmt::ForEach(name, item_type, array, len, body) => {
+                let start = cg.new_label();
+                let step = cg.new_label();
+                let end = cg.new_label();
+                let array_type = ValueType::Pointer(Box::new(item_type.clone()));
+
+                cg.enter_scope();
+
+                let address = TypedPrimaryExpr::Ref(array_type.clone(), array.clone());
+                let address = TypedExpr::Primary(array_type.clone(), Box::new(address));
+                TypedStmt::VarDeclr(FOR_ARRAY.to_string(), array_type.clone(), Some(address)).generate(cg);
+                TypedStmt::VarDeclr(FOR_INDEX.to_string(), ValueType::U16, Some(u16_literal(0))).generate(cg);
+                cg.declare_var(name.clone(), item_type.clone());
+
+                let index = var_expr(FOR_INDEX, &ValueType::U16);
+                let item = TypedVariable::Array(item_type.clone(), Box::new(TypedVariable::Id(array_type, FOR_ARRAY.to_string(), 0)), index.clone());
+                let item = TypedExpr::Primary(item_type.clone(), Box::new(TypedPrimaryExpr::Variable(item)));
+
+                cg.write_label(start.clone());
+                binary_expr(TypedExpr::Comparison, "<", index.clone(), u16_literal(*len)).generate_onto_stack(cg);
+                cg.pop_condition();
+                cg.write_instruction(Instruction::Bze(end.clone()));
+
+                let copy = TypedExpr::Assign(item_type.clone(), Box::new(var_expr(name, item_type)), Box::new(item));
+                TypedStmt::ExprStmt(copy).generate(cg);
+
+                cg.loop_labels.push((step.clone(), end.clone()));
+                body.generate(cg);
+                cg.loop_labels.pop();
+
+                cg.write_label(step);
+                increment(index, &ValueType::U16).generate(cg);
+                cg.write_instruction(Instruction::Jmp(start));
+                cg.write_label(end);
+
+                cg.leave_scope();
+            }
+
+            /*
+                condition
+                bze else
+                true branch
+                jmp end
+            else:
+                false branch
+            end:
+            */
+            TypedStmt::IfStmt(condition, t_branch, f_branch) => {
+                let else_label = cg.new_label();
+                let end = cg.new_label();
+
+                condition.generate_onto_stack(cg);
+                cg.pop_condition();
+                cg.write_instruction(Instruction::Bze(else_label.clone()));
+
+                t_branch.generate(cg);
+                cg.write_instruction(Instruction::Jmp(end.clone()));
+
+                cg.write_label(else_label);
+                if let Some(f_branch) = f_branch {
+                    f_branch.generate(cg);
+                }
+
+                cg.write_label(end);
+            }
+
+            //the value is left on the stack for the loop expression
+            TypedStmt::BreakStmt(loops_left, value) => {
+                if let Some(value) = value {
+                    value.generate_onto_stack(cg);
+                }
+
+                let (_, end) = cg.enclosing_loop(*loops_left);
+                cg.write_instruction(Instruction::Jmp(end));
+            }
+
+            TypedStmt::ContinueStmt(loops_left) => {
+                let (step, _) = cg.enclosing_loop(*loops_left);
+                cg.write_instruction(Instruction::Jmp(step));
+            }
+
+            //the value is copied to the slot the caller reserved below the saved frame
+            TypedStmt::ReturnStmt(value) => {
+                let template = cg.current_fn.clone().expect("should have been caught");
+                let size = value.final_type().size(&cg.defined_types);
+                let slot = (SAVED_FRAME_SIZE + size).wrapping_neg();
+
+                value.generate_onto_stack(cg);
+
+                for nth in 0..size {
+                    cg.ld_sp_minus_to_reg(R0, size - nth);
+                    cg.set_mar_bp_plus(slot.wrapping_add(nth));
+                    cg.write_instruction(Instruction::Str(R0));
+                }
+
+                cg.write_instruction(Instruction::Jmp(fn_return_label(&template.name)));
+            }
+
+            //functions are generated separately, statics are placed before anything runs,
+            //constants and types take no space
+            TypedStmt::FnDeclr(..) => {}
+            TypedStmt::StaticDeclr(..) => {}
+            TypedStmt::ConstDeclr(..) => {}
+            TypedStmt::CustomTypeDeclr => {}
+        }
+    }
+
+    ///Frame space needed by every variable declared in the statement,
+    ///including the bodies of loop expressions but not nested functions
+    pub fn locals_size(&self, defined_types: &Vec<CustomType>) -> u16 {
+        match self {
+            TypedStmt::VarDeclr(_, var_type, init) => {
+                let init_size = match init {
+                    Some(init) => init.locals_size(defined_types),
+                    None => 0,
+                };
+
+                var_type.size(defined_types) + init_size
+            }
+
+            TypedStmt::Block(stmts) => stmts.iter().map(|x| x.locals_size(defined_types)).sum(),
+
+            TypedStmt::ExprStmt(expr) | TypedStmt::ReturnStmt(expr) | TypedStmt::BreakStmt(_, Some(expr)) => expr.locals_size(defined_types),
+
+            TypedStmt::LoopStmt(body) => body.locals_size(defined_types),
+            TypedStmt::WhileStmt(cond, body) => cond.locals_size(defined_types) + body.locals_size(defined_types),
+
+            //the loop variable and the end
+            TypedStmt::ForRange(_, var_type, start, end, _, body) => {
+                2 * var_type.size(defined_types) + start.locals_size(defined_types) + end.locals_size(defined_types) + body.locals_size(defined_types)
+            }
+
+            //the item, the array's address and the index
+            TypedStmt::ForEach(_, item_type, array, _, body) => {
+                item_type.size(defined_types) + 4 + array.locals_size(defined_types) + body.locals_size(defined_types)
+            }
+
+            TypedStmt::IfStmt(cond, t_branch, f_branch) => {
+                let f_size = match f_branch {
+                    Some(f_branch) => f_branch.locals_size(defined_types),
+                    None => 0,
+                };
+
+                cond.locals_size(defined_types) + t_branch.locals_size(defined_types) + f_size
+            }
+
+            _ => 0
+        }
+    }
+}
+
+impl TypedExpr {
+    ///Frame space needed by the bodies of the loop expressions inside
+    pub fn locals_size(&self, defined_types: &Vec<CustomType>) -> u16 {
+        match self {
+            TypedExpr::Loop(_, body) => body.locals_size(defined_types),
+
+            TypedExpr::Assign(_, left, right) | TypedExpr::CompoundAssign(_, left, _, right) | TypedExpr::Shift(_, left, _, right) => {
+                left.locals_size(defined_types) + right.locals_size(defined_types)
+            }
+
+            TypedExpr::Equality(_, binary) | TypedExpr::Comparison(_, binary) | TypedExpr::Term(_, binary) | TypedExpr::Factor(_, binary) => {
+                binary.left.locals_size(defined_types) + binary.right.locals_size(defined_types)
+            }
+
+            TypedExpr::Unary(_, _, value) | TypedExpr::Cast(_, value) => value.locals_size(defined_types),
+
+            TypedExpr::FnCall(_, _, args) => args.items.iter().map(|x| x.locals_size(defined_types)).sum(),
+
+            TypedExpr::If(_, branches) => {
+                branches.0.locals_size(defined_types) + branches.1.locals_size(defined_types) + branches.2.locals_size(defined_types)
+            }
+
+            TypedExpr::Primary(_, primary) => match &**primary {
+                TypedPrimaryExpr::Grouping(body) => body.locals_size(defined_types),
+                TypedPrimaryExpr::Variable(var) | TypedPrimaryExpr::Ref(_, var) => var.locals_size(defined_types),
+                _ => 0
+            }
+        }
+    }
+}
+
+impl TypedVariable {
+    ///Frame space needed by the loop expressions in its indices
+    pub fn locals_size(&self, defined_types: &Vec<CustomType>) -> u16 {
+        match self {
+            TypedVariable::Id(..) => 0,
+            TypedVariable::StructField(_, access) => access.0.locals_size(defined_types) + access.1.locals_size(defined_types),
+            TypedVariable::Array(_, head, index) => head.locals_size(defined_types) + index.locals_size(defined_types),
+            TypedVariable::Deref(_, pointer) => pointer.locals_size(defined_types),
+        }
+    }
+}
+
+impl CodeGenerator {
+    /*
+    start:
+        body
+        jmp start
+    end:
+    */
+    pub fn generate_loop(&mut self, body: &TypedStmt) {
+        let start = self.new_label();
+        let end = self.new_label();
+
+        self.write_label(start.clone());
+
+        self.loop_labels.push((start.clone(), end.clone()));
+        body.generate(self);
+        self.loop_labels.pop();
+
+        self.write_instruction(Instruction::Jmp(start));
+        self.write_label(end);
+    }
+
+    ///Labels of the loop `loops_left` out from the innermost one
+    fn enclosing_loop(&self, loops_left: usize) -> (String, String) {
+        let index = self.loop_labels.len() - 1 - loops_left;
+        self.loop_labels[index].clone()
+    }
+}
+
+fn var_expr(name: &str, var_type: &ValueType) -> TypedExpr {
+    let var = TypedVariable::Id(var_type.clone(), name.to_string(), 0);
+    TypedExpr::Primary(var_type.clone(), Box::new(TypedPrimaryExpr::Variable(var)))
+}
+
+fn u16_literal(value: u16) -> TypedExpr {
+    TypedExpr::Primary(ValueType::U16, Box::new(TypedPrimaryExpr::NumLiteral(NumLiteral::U16(value))))
+}
+
+///a comparison or equality, which are u8 booleans
+fn binary_expr(variant: fn(ValueType, Box<TypedBinaryExpr>) -> TypedExpr, op: &str, left: TypedExpr, right: TypedExpr) -> TypedExpr {
+    variant(ValueType::U8, Box::new(TypedBinaryExpr { left, operator: op.to_string(), right }))
+}
+
+///`var += 1` as a statement
+fn increment(var: TypedExpr, var_type: &ValueType) -> TypedStmt {
+    let one = NumLiteral::U8(1).try_implicit_cast(var_type).expect("loop variables are numbers");
+    let one = TypedExpr::Primary(var_type.clone(), Box::new(TypedPrimaryExpr::NumLiteral(one)));
+
+    TypedStmt::ExprStmt(TypedExpr::CompoundAssign(var_type.clone(), Box::new(var), "+".to_string(), Box::new(one)))
+}
@@ -20,7 +20,7 @@ pub fn create_two_values(cg: &mut CodeGenerator, x: &TypedExpr, y: &TypedExpr) -
     if x.value_known_at_compile() {
         x_location = ValueLocation::Immediate;
     } else if x.location_known_at_compile() {
-        x_location = ValueLocation::BpPlus(x.get_var_offset_from_bp(cg));
+        x_location = x.get_location(cg);
     } else {
         x.generate_onto_stack(cg);
         x_location = ValueLocation::SpMinus(x_size);
@@ -30,7 +30,7 @@ pub fn create_two_values(cg: &mut CodeGenerator, x: &TypedExpr, y: &TypedExpr) -
     if y.value_known_at_compile() {
         y_location = ValueLocation::Immediate;
     } else if y.location_known_at_compile() {
-        y_location = ValueLocation::BpPlus(y.get_var_offset_from_bp(cg));
+        y_location = y.get_location(cg);
     } else {
         y.generate_onto_stack(cg);
         y_location = ValueLocation::SpMinus(y_size);
@@ -65,7 +65,7 @@ pub fn create_value(cg: &mut CodeGenerator, x: &TypedExpr) -> Value {
     if x.value_known_at_compile() {
         x_location = ValueLocation::Immediate;
     } else if x.location_known_at_compile() {
-        x_location = ValueLocation::BpPlus(x.get_var_offset_from_bp(cg));
+        x_location = x.get_location(cg);
     } else {
         x.generate_onto_stack(cg);
         x_location = ValueLocation::SpMinus(x_size);
@@ -77,16 +77,19 @@ pub fn create_value(cg: &mut CodeGenerator, x: &TypedExpr) -> Value {
         value_size: x_size}
 }
 
-pub fn clean_after_two_values(cg: &mut CodeGenerator, x: Value, y: Value) {
-    let mut amount_to_decrease_sp: u16 = 0;
-
+pub fn clean_after_value(cg: &mut CodeGenerator, x: Value) {
     if let ValueLocation::SpMinus(n) = x.location {
-        amount_to_decrease_sp += n;
+        cg.drop_from_stack(n);
     }
+}
 
-    if let ValueLocation::SpMinus(n) = y.location {
-        amount_to_decrease_sp += n;
-    }
+///X's offset on the stack already counts Y when both were put there
+pub fn clean_after_two_values(cg: &mut CodeGenerator, x: Value, y: Value) {
+    let amount_to_decrease_sp = match (x.location, y.location) {
+        (ValueLocation::SpMinus(n), _) => n,
+        (_, ValueLocation::SpMinus(n)) => n,
+        _ => 0,
+    };
 
     if amount_to_decrease_sp != 0 {
         cg.decrease_sp_by(amount_to_decrease_sp, R0);
@@ -97,134 +100,81 @@ impl Value {
     pub fn ld_nth_byte_to_reg(&self, reg: REG, nth: u16, cg: &mut CodeGenerator) {
         match self.location {
             ValueLocation::Immediate => {
-                let value = self.expr.get_nth_byte(nth, cg);
+                let value = self.expr.get_nth_byte(nth);
                 cg.write_instruction(Instruction::Imr(reg, value));
             }
 
             ValueLocation::BpPlus(offset_from_base) => {
-                if offset_from_base + nth as i16 >= 0 {
-                    let total_offset = offset_from_base as u16 + nth;
-
-                    cg.ld_bp_plus_n_to_reg(reg, total_offset);
+                let total_offset = (offset_from_base as u16).wrapping_add(nth);
+                cg.ld_bp_plus_n_to_reg(reg, total_offset);
+            }
 
-                } else {
-                    todo!()
-                }
+            ValueLocation::Absolute(address) => {
+                cg.ld_abs_to_reg(reg, address.wrapping_add(nth));
             }
 
+            //SpMinus is where the first byte is
             ValueLocation::SpMinus(offset_from_sp) => {
-                cg.ld_sp_minus_to_reg(reg, offset_from_sp + nth);
+                cg.ld_sp_minus_to_reg(reg, offset_from_sp - nth);
             }
         }
     }
+
+    ///copies the value to a scratch area, overwrites R0
+    pub fn ld_to_scratch(&self, scratch: u16, cg: &mut CodeGenerator) {
+        for nth in 0..self.value_size {
+            self.ld_nth_byte_to_reg(R0, nth, cg);
+            cg.str_reg_to_abs(R0, scratch + nth);
+        }
+    }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum ValueLocation {
     SpMinus(u16),
     BpPlus(i16),
+    Absolute(u16),
     Immediate,
 }
 
-impl TypedExpr {
-    pub fn get_var_offset_from_bp(&self, cg: &CodeGenerator) -> i16 {
-        if let TypedExpr::Primary(_, primary) = self {
-            let var_offset =
-            match &**primary {
-                TypedPrimaryExpr::Grouping(body) => body.get_var_offset_from_bp(cg),
-
-                TypedPrimaryExpr::Variable(var) => var.get_total_offset(),
-
-                TypedPrimaryExpr::Ref(_, _, var) => var.get_total_offset(),
-            
-                _ => unreachable!()
-            };
-
-            let (_, var_offset_from_bp) = cg.get_var(self.get_first_id());
-
-            return var_offset + var_offset_from_bp as i16
-        }
-
-        //should only be called on expressions that contain only vars
-        unreachable!()
-    }
-
-    pub fn get_nth_byte(&self, nth: u16, cg: &CodeGenerator) -> u8 {
+impl ValueLocation {
+    ///the location `n` bytes further, which can't be on the stack
+    pub fn plus(self, n: u16) -> ValueLocation {
         match self {
-            TypedExpr::Primary(_, primary) => primary.get_nth_byte(nth, cg),
-
-            TypedExpr::Cast(to_type, original_expr) => {
-                let original_type = original_expr.final_type();
-
-                if original_type.size(&cg.defined_types) < to_type.size(&cg.defined_types) && to_type.is_signed_type(){
-                    //TODO casting to signed values
-                    todo!()
-                } else {
-                    original_expr.get_nth_byte(nth, cg)
-                }
-            }
-
-            _ => panic!("get_nth_byte() should not be called on non-immediate values")
+            ValueLocation::BpPlus(offset) => ValueLocation::BpPlus(offset.wrapping_add(n as i16)),
+            ValueLocation::Absolute(address) => ValueLocation::Absolute(address.wrapping_add(n)),
+            _ => unreachable!()
         }
     }
+}
 
-    pub fn get_first_id(&self) -> String {
-        //ugly ass function, most of it needs to be unreachable but i realized that after i wrote it
-        
-        match self {
-            TypedExpr::Assign(_, _, value) => value.get_first_id(),
-
-            TypedExpr::Cast(_, value) => value.get_first_id(),
-
-            TypedExpr::Comparison(_, binary) => binary.get_first_id(),
-
-            TypedExpr::Equality(_, binary) => binary.get_first_id(),
-
-            TypedExpr::Term(_, binary) => binary.get_first_id(),
-
-            TypedExpr::FnCall(_, name, _ ) => name.clone(),
-
-            TypedExpr::Shift(_, value, _, _) => value.get_first_id(),
-
-            TypedExpr::Unary(_, _, value) => value.get_first_id(),
-
-            TypedExpr::Primary(_, primary) => {
-                match &**primary {
-                    TypedPrimaryExpr::Grouping(body) => body.get_first_id(),
-
-                    TypedPrimaryExpr::EnumVariant(name, _) => name.name.clone(),
-
-                    TypedPrimaryExpr::NumLiteral(_) => unreachable!(),
+impl TypedExpr {
+    ///should only be called if the location is known at compile time
+    pub fn get_location(&self, cg: &CodeGenerator) -> ValueLocation {
+        if let TypedExpr::Primary(_, primary) = self {
+            match &**primary {
+                TypedPrimaryExpr::Grouping(body) => return body.get_location(cg),
 
-                    TypedPrimaryExpr::Variable(var) => var.get_first_id(),
+                TypedPrimaryExpr::Variable(var) => return var.get_location(cg),
 
-                    TypedPrimaryExpr::Ref(_, _, _) => unreachable!(),
-                }
+                _ => {}
             }
         }
-    }
-}
-
-impl TypedPrimaryExpr {
-    pub fn get_nth_byte(&self, nth: u16, cg: &CodeGenerator) -> u8 {
-        match self {
-            TypedPrimaryExpr::EnumVariant(_, variant) => {
-                if nth == 0 {
-                    variant.1
-                } else {
-                    0
-                }
-            }
-
-            TypedPrimaryExpr::Grouping(body) => body.get_nth_byte(nth, cg),
 
-            TypedPrimaryExpr::NumLiteral(num) => num.get_nth_byte(nth),
+        unreachable!()
+    }
 
-            _ => panic!("not an immediate value, cannot get nth byte")
+    ///should only be called if the value is known at compile time
+    pub fn get_nth_byte(&self, nth: u16) -> u8 {
+        match self.const_value() {
+            Some(value) => value.get_nth_byte(nth),
+            None => panic!("get_nth_byte() should not be called on non-immediate values")
         }
     }
 }
 
 impl NumLiteral {
+    #[allow(clippy::unnecessary_cast)]
     pub fn get_nth_byte(&self, nth: u16) -> u8 {
         match self {
             NumLiteral::U8(num) => {
@@ -310,35 +260,41 @@ impl NumLiteral {
     }
 }
 
-impl TypedBinaryExpr {
-    pub fn get_first_id(&self) -> String {
-        return self.left.get_first_id()
+impl TypedVariable {
+    ///should only be called if the location is known at compile time
+    pub fn get_location(&self, cg: &CodeGenerator) -> ValueLocation {
+        let (_, head_location) = cg.get_var(self.get_first_id());
+        let offset = self.get_total_offset(cg);
+
+        match head_location {
+            ValueLocation::BpPlus(n) => ValueLocation::BpPlus(n.wrapping_add(offset)),
+
+            ValueLocation::Absolute(address) => ValueLocation::Absolute(address.wrapping_add(offset as u16)),
+
+            _ => unreachable!()
+        }
     }
-}
 
-impl TypedVariable {
-    pub fn get_total_offset(&self) -> i16 {
+    ///offset from the first byte of the head variable,
+    ///array indexes need to be known at compile time
+    pub fn get_total_offset(&self, cg: &CodeGenerator) -> i16 {
         match self {
             TypedVariable::Id(_, _, offset) => *offset as i16,
 
             TypedVariable::StructField(_, body) => {
                 let (head, tail) = &**body;
 
-                (head.get_total_offset() + tail.get_total_offset()) as i16
+                head.get_total_offset(cg).wrapping_add(tail.get_total_offset(cg))
             }
 
-            TypedVariable::Array(_, _, index) => {
-                if let TypedExpr::Primary(_, primary) = index {
-                    if let TypedPrimaryExpr::NumLiteral(num) = &**primary {
-                        if let NumLiteral::I16(offset) = num {
-                            return *offset
-                        }
-                    }
-                }
+            TypedVariable::Array(item_type, head, index) => {
+                let index_value = i16::from_le_bytes([index.get_nth_byte(0), index.get_nth_byte(1)]);
+                let item_size = item_type.size(&cg.defined_types) as i16;
 
-                //should only be called if index is a literal
-                unreachable!()
+                head.get_total_offset(cg).wrapping_add(index_value.wrapping_mul(item_size))
             }
+
+            TypedVariable::Deref(_, _) => unreachable!()
         }
     }
 
@@ -348,7 +304,9 @@ impl TypedVariable {
 
             TypedVariable::Array(_, head, _) => head.get_first_id(),
 
-            TypedVariable::StructField(_, body) => body.0.get_first_id()
+            TypedVariable::StructField(_, body) => body.0.get_first_id(),
+
+            TypedVariable::Deref(_, _) => unreachable!()
         }
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,134 @@
+use mycc::Emit;
+
+use std::path::PathBuf;
+
+pub const USAGE: &str = "usage: mycc <input> [-o <output>] [--emit=tokens|ast|typed-ast|asm|bin]
+       mycc run <input>
+       mycc disasm <binary>
+inputs ending in .asm are read as assembly";
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    Compile(Options),
+    Run(PathBuf),
+    Disasm(PathBuf),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Options {
+    pub input: PathBuf,
+    pub output: Option<PathBuf>,
+    pub emit: Emit,
+}
+
+impl Command {
+    pub fn from_args(args: &[String]) -> Result<Command, String> {
+        match args.first().map(|x| x.as_str()) {
+            Some("run") => Ok(Command::Run(single_input(&args[1..])?)),
+            Some("disasm") => Ok(Command::Disasm(single_input(&args[1..])?)),
+
+            _ => Ok(Command::Compile(Options::from_args(args)?))
+        }
+    }
+}
+
+///subcommands take exactly one input path
+fn single_input(args: &[String]) -> Result<PathBuf, String> {
+    match args {
+        [input] if !input.starts_with('-') => Ok(PathBuf::from(input)),
+        [] => Err("No Input File".to_string()),
+        _ => Err(format!("Unexpected Argument: {}", args[args.len() - 1]))
+    }
+}
+
+impl Options {
+    pub fn from_args(args: &[String]) -> Result<Options, String> {
+        let mut input: Option<PathBuf> = None;
+        let mut output: Option<PathBuf> = None;
+        let mut emit = Emit::Bin;
+
+        let mut ptr: usize = 0;
+        while ptr < args.len() {
+            let arg = args[ptr].as_str();
+
+            if arg == "-o" {
+                ptr += 1;
+                match args.get(ptr) {
+                    Some(path) => output = Some(PathBuf::from(path)),
+                    None => return Err("Expected Output Path after -o".to_string())
+                }
+            } else if let Some(name) = arg.strip_prefix("--emit=") {
+                emit = match Emit::from_name(name) {
+                    Some(t) => t,
+                    None => return Err(format!("Unknown Emit Kind: {}", name))
+                };
+            } else if arg.starts_with('-') {
+                return Err(format!("Unknown Option: {}", arg))
+            } else {
+                if input.is_some() {
+                    return Err(format!("Unexpected Argument: {}", arg))
+                }
+
+                input = Some(PathBuf::from(arg));
+            }
+
+            ptr += 1;
+        }
+
+        let input = match input {
+            Some(t) => t,
+            None => return Err("No Input File".to_string())
+        };
+
+        //binaries are never written to the terminal
+        if output.is_none() && !emit.is_text() {
+            output = Some(input.with_extension("bin"));
+        }
+
+        Ok(Options { input, output, emit })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|x| x.to_string()).collect()
+    }
+
+    #[test]
+    fn test_options_from_args() {
+        let opts = Options::from_args(&args(&["prog.mycc", "-o", "out.asm", "--emit=asm"])).unwrap();
+        assert_eq!(opts.input, PathBuf::from("prog.mycc"));
+        assert_eq!(opts.output, Some(PathBuf::from("out.asm")));
+        assert_eq!(opts.emit, Emit::Asm);
+
+        let opts = Options::from_args(&args(&["prog.mycc"])).unwrap();
+        assert_eq!(opts.output, Some(PathBuf::from("prog.bin")));
+        assert_eq!(opts.emit, Emit::Bin);
+
+        let opts = Options::from_args(&args(&["--emit=tokens", "prog.mycc"])).unwrap();
+        assert_eq!(opts.output, None);
+
+        assert!(Options::from_args(&args(&[])).is_err());
+        assert!(Options::from_args(&args(&["a", "b"])).is_err());
+        assert!(Options::from_args(&args(&["a", "--emit=exe"])).is_err());
+        assert!(Options::from_args(&args(&["a", "-o"])).is_err());
+    }
+
+    #[test]
+    fn test_command_from_args() {
+        let cmd = Command::from_args(&args(&["run", "prog.mycc"])).unwrap();
+        assert_eq!(cmd, Command::Run(PathBuf::from("prog.mycc")));
+
+        let cmd = Command::from_args(&args(&["prog.mycc", "--emit=asm"])).unwrap();
+        assert!(matches!(cmd, Command::Compile(_)));
+
+        let cmd = Command::from_args(&args(&["disasm", "rom.bin"])).unwrap();
+        assert_eq!(cmd, Command::Disasm(PathBuf::from("rom.bin")));
+
+        assert!(Command::from_args(&args(&["run"])).is_err());
+        assert!(Command::from_args(&args(&["run", "a", "b"])).is_err());
+    }
+}
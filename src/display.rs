@@ -1,8 +1,18 @@
 use std::fmt::Display;
 
+use crate::cgen::asm_parser::AsmParseErr;
+use crate::cgen::assembler::AssembleErr;
+use crate::cgen::disassembler::DisassembleErr;
+use crate::cgen::instruction::*;
+use crate::cgen::CodegenErr;
+use crate::emulator::EmulatorErr;
 use crate::expr::*;
+use crate::lexer::LexErr;
+use crate::parser::ParseError;
+use crate::semantics::semantic_err::SemanticErr;
 use crate::stmt::*;
 use crate::token::*;
+use crate::types::*;
 
 impl Display for TypeDeclr {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -14,6 +24,26 @@ impl Display for TypeDeclr {
     }
 }
 
+impl Display for ValueType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::U8 => write!(f, "u8"),
+            Self::I8 => write!(f, "i8"),
+            Self::U16 => write!(f, "u16"),
+            Self::I16 => write!(f, "i16"),
+            Self::U32 => write!(f, "u32"),
+            Self::I32 => write!(f, "i32"),
+            Self::U64 => write!(f, "u64"),
+            Self::I64 => write!(f, "i64"),
+            Self::Void => write!(f, "void"),
+            Self::Pointer(t) => write!(f, "->{}", t),
+            Self::Array(item_type, size) => write!(f, "[{}; {}]", item_type, size),
+            Self::CustomStruct(name) => write!(f, "{}", name),
+            Self::CustomEnum(template) => write!(f, "{}", template.name),
+        }
+    }
+}
+
 impl Display for Parameters {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         for p in &self.params {
@@ -32,6 +62,8 @@ impl Display for Token {
             Self::Cond(s) => write!(f, "{}", s),
             Self::Id(s) => write!(f, "{}", s),
             Self::Lit(s) => write!(f, "{}", s),
+            Self::Str(s) => write!(f, "{}", s),
+            Self::Label(s) => write!(f, "{}", s),
             Self::ParenOpen => write!(f, "("),
             Self::ParenClose => write!(f, ")"),
             Self::SquareOpen => write!(f, "["),
@@ -43,11 +75,18 @@ impl Display for Token {
             Self::Comma => write!(f, ","),
             Self::Period => write!(f, "."),
             Self::Arrow => write!(f, "->"),
+            Self::DocComment(s) => write!(f, "///{}", s),
             Self::EOF => write!(f, "EOF"),
         }
     }
 }
 
+impl Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}, Found: {}", self.message, self.found)
+    }
+}
+
 impl Display for Lexeme {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}", self.tok)
@@ -74,17 +113,37 @@ impl Display for Expr {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Self::Assign(t) => write!(f, "{} = {}", t.left, t.right),
+            Self::CompoundAssign(t) => write!(f, "{} {} {}", t.left, t.operator, t.right),
             Self::Equality(t) => write!(f, "{}", t),
             Self::Comparison(t) => write!(f, "{}", t),
             Self::Term(t) => write!(f, "{}", t),
-            Self::Shift(t, t2, t3) => write!(f, "({} {} {})", t, t2, t3),
+            Self::Factor(t) => write!(f, "{}", t),
+            Self::Shift(t) => write!(f, "{}", t),
             Self::Unary(t, t2) => write!(f, "({} {})", t, t2),
             Self::Cast(t, t2, t3) => write!(f, "({} {} {})", t, t2, t3),
             Self::FnCall(t, t2) => write!(f, "(Call fn {} Args: {})", t, t2),
+            Self::If(_, t) => write!(f, "(If {} Then {} Else {})", t.0, t.1, t.2),
+            Self::Loop(_, label, b) => {
+                write_label(f, label)?;
+                write!(f, "(Loop Body:\n{})", b)
+            }
             Self::Primary(t) => {
                 match &**t {
                     PrimaryExpr::Grouping(t) => write!(f, "{}", t),
                     PrimaryExpr::NumLiteral(t, _) => write!(f, "{}", t),
+                    PrimaryExpr::StrLiteral(_, t) => write!(f, "{}", t),
+                    PrimaryExpr::StructLiteral(t, fields) => {
+                        write!(f, "({} {{", t)?;
+                        for (name, value) in fields {
+                            write!(f, " {}: {}", name, value)?;
+                        }
+                        write!(f, " }})")
+                    }
+                    PrimaryExpr::ArrayLiteral(_, items) => {
+                        let items: Vec<String> = items.iter().map(|x| x.to_string()).collect();
+                        write!(f, "[{}]", items.join(", "))
+                    }
+                    PrimaryExpr::ArrayRepeat(_, t, n) => write!(f, "[{}; {}]", t, n),
                     PrimaryExpr::EnumVariant(t, t2) => write!(f, "({}::{})", t, t2),
                     PrimaryExpr::Variable(t) => write!(f, "{}", t),
                     PrimaryExpr::Ref(t, t2) => write!(f, "Ref {} on {}", t, t2),
@@ -110,6 +169,7 @@ impl Display for Variable {
             Self::Id(t) => write!(f, "{}", t),
             Self::StructField(t) => write!(f, "{}.{}", t.0, t.1),
             Self::Array(t, t2) => write!(f, "{}[{}]", t, t2),
+            Self::Deref(_, t) => write!(f, "(* {})", t),
         }
     }
 }
@@ -130,6 +190,10 @@ impl Display for Stmt {
                 }
             }
 
+            Self::StaticDeclr(name, t, v) => write!(f, "Declare Static: {} Type: {} Value: {}", name, t, v)?,
+
+            Self::ConstDeclr(name, t, v) => write!(f, "Declare Const: {} Type: {} Value: {}", name, t, v)?,
+
             Self::Block(b) => {
                 write!(f, "Block:")?;
                 for s in b {
@@ -137,7 +201,22 @@ impl Display for Stmt {
                 }
             } 
 
-            Self::BreakStmt(_) => write!(f, "Break")?,
+            Self::BreakStmt(_, label, value) => {
+                write!(f, "Break")?;
+                if let Some(label) = label {
+                    write!(f, " {}", label)?;
+                }
+                if let Some(value) = value {
+                    write!(f, " With {}", value)?;
+                }
+            }
+
+            Self::ContinueStmt(_, label) => {
+                write!(f, "Continue")?;
+                if let Some(label) = label {
+                    write!(f, " {}", label)?;
+                }
+            }
 
             Self::EnumDeclr(n, v) => {
                 write!(f, "Declare Enum: {} Variants:", n)?;
@@ -148,7 +227,10 @@ impl Display for Stmt {
 
             Self::ExprStmt(e) => write!(f, "ExprStmt {}", e)?,
 
-            Self::FnDeclr(n, arg, ret_t, b) => write!(f, "Declare Fn: {} Params: {} RetType: {} Body: {}", n, arg, ret_t, b)?,
+            Self::FnDeclr(n, arg, ret_t, b, doc) => {
+                write_doc(f, doc)?;
+                write!(f, "Declare Fn: {} Params: {} RetType: {} Body: {}", n, arg, ret_t, b)?
+            }
 
             Self::IfStmt(c, t, fb) => {
                 write!(f, "If {} Do\n{}", c, t)?;
@@ -157,15 +239,180 @@ impl Display for Stmt {
                 }
             }
 
-            Self::LoopStmt(b) => write!(f, "Loop Body:\n{}", b)?,
+            Self::LoopStmt(label, b) => {
+                write_label(f, label)?;
+                write!(f, "Loop Body:\n{}", b)?
+            }
 
             Self::ReturnStmt(_, e) => write!(f, "Return {}", e)?,
 
-            Self::StructDeclr(n, fields) => write!(f, "Declare Struct: {} Fields: {}", n, fields)?,
+            Self::StructDeclr(n, fields, doc) => {
+                write_doc(f, doc)?;
+                write!(f, "Declare Struct: {} Fields: {}", n, fields)?
+            }
+
+            Self::WhileStmt(label, c, b) => {
+                write_label(f, label)?;
+                write!(f, "While {} Do: \n{}", c, b)?
+            }
 
-            Self::WhileStmt(c, b) => write!(f, "While {} Do: \n{}", c, b)?
+            Self::ForStmt(label, v, _, header, b) => {
+                let (iterated, range_end) = &**header;
+                write_label(f, label)?;
+                write!(f, "For {} In {}", v, iterated)?;
+                if let Some((op, end)) = range_end {
+                    write!(f, "{}{}", op, end)?;
+                }
+                write!(f, " Do: \n{}", b)?
+            }
         }
 
         Ok(())
     }
-}
\ No newline at end of file
+}
+
+fn write_label(f: &mut std::fmt::Formatter<'_>, label: &Option<Lexeme>) -> std::fmt::Result {
+    if let Some(label) = label {
+        write!(f, "{}: ", label)?;
+    }
+
+    Ok(())
+}
+
+fn write_doc(f: &mut std::fmt::Formatter<'_>, doc: &Option<String>) -> std::fmt::Result {
+    if let Some(doc) = doc {
+        for line in doc.lines() {
+            writeln!(f, "Doc: {}", line)?;
+        }
+    }
+
+    Ok(())
+}
+
+impl Display for Instruction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.mnemonic())?;
+
+        match self {
+            Self::Add(r) | Self::Sub(r) | Self::Adc(r) | Self::Sbc(r) |
+            Self::Ror(r) | Self::Nor(r) | Self::And(r) | Self::Rmov(r) |
+            Self::Amov(r) | Self::Str(r) | Self::Ld(r) | Self::In(r) |
+            Self::Out(r) | Self::Push(r) | Self::Pop(r) => write!(f, " {}", reg_name(*r)),
+
+            Self::Jmp(l) | Self::Bca(l) | Self::Bnc(l) | Self::Bze(l) |
+            Self::Bnz(l) | Self::Bsi(l) | Self::Bpa(l) => write!(f, " {}", l),
+
+            Self::Ima(v) => write!(f, " {}", v),
+
+            Self::Imr(r, v) => write!(f, " {} {}", reg_name(*r), v),
+
+            Self::Stc | Self::Clc | Self::Zac | Self::Spc | Self::Nop | Self::Hlt => Ok(()),
+        }
+    }
+}
+
+impl Display for AssemblyCommand {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Label(l) => write!(f, "{}:", l),
+            Self::Comment(c) => write!(f, "; {}", c),
+            Self::Instruction(i) => write!(f, "    {}", i),
+            Self::Byte(b) => write!(f, "    .byte {:#04x}", b),
+        }
+    }
+}
+
+impl Display for LexErr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UnknownToken => write!(f, "Unknown Token"),
+            Self::UnterminatedComment => write!(f, "Unterminated Block Comment"),
+            Self::MalformedLiteral => write!(f, "Malformed Number Literal"),
+            Self::UnterminatedString => write!(f, "Unterminated String Literal"),
+        }
+    }
+}
+
+impl Display for SemanticErr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NoStructField(t, l) => write!(f, "Struct {} has no field {}", t.name, l),
+            Self::MissingField(field, _) => write!(f, "Missing field {} in struct literal", field),
+            Self::DuplicateField(l) => write!(f, "Field {} is given more than once", l),
+            Self::NoEnumVariant(t, l) => write!(f, "Enum {} has no variant {}", t.name, l),
+            Self::WrongAccess(t, _) => write!(f, "{} is not an enum", t.name()),
+            Self::UnknownType(l) => write!(f, "Unknown type {}", l),
+            Self::NotAStruct(l) => write!(f, "{} is not a struct", l),
+            Self::NotAnArray(l) => write!(f, "{} is not an array or pointer", l),
+            Self::UndeclaredVar(l) => write!(f, "Undeclared variable {}", l),
+            Self::WrongType(should, is, _) => write!(f, "Expected type {}, found {}", should, is),
+            Self::UsedId(l) => write!(f, "{} is already declared", l),
+            Self::CantDeref(_) => write!(f, "Can't dereference a value that is not a pointer"),
+            Self::NotAVar(l) => write!(f, "Left side of {} can't be assigned to", l),
+            Self::ShiftAmountErr(l) => write!(f, "Shift amount of {} is out of range", l),
+            Self::UndeclaredFn(l) => write!(f, "Undeclared function {}", l),
+            Self::FnArityErr(l) => write!(f, "Wrong number of arguments to {}", l),
+            Self::DuplicateParams(l) => write!(f, "Parameter {} is declared more than once", l),
+            Self::CantReturn(_) => write!(f, "Can't return outside of a function"),
+            Self::CantBreak(l) => write!(f, "Can't {} outside of a loop", l),
+            Self::UnknownLabel(l) => write!(f, "Unknown loop label {}", l),
+            Self::EnumDuplicateVariants(l) => write!(f, "Variant {} is declared more than once", l),
+            Self::RecursiveStruct(name) => write!(f, "Struct {} contains itself", name),
+            Self::CantCast(_) => write!(f, "Invalid cast"),
+            Self::CantOp(l) => write!(f, "Operator {} can't be used on these types", l),
+            Self::CantDeclareThisInLocalScope(l) => write!(f, "{} can only be declared at the top level", l),
+            Self::NotConstant(_) => write!(f, "Value is not known at compile time"),
+            Self::ArraySizeErr(_) => write!(f, "Array size must be a constant between 0 and 65535"),
+            Self::DivByZero(_) => write!(f, "Constant division by zero"),
+        }
+    }
+}
+
+impl Display for CodegenErr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::DataSegmentFull(size) => write!(f, "Statics and string literals take {} bytes, the data segment is full", size),
+        }
+    }
+}
+
+impl Display for AsmParseErr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UnknownMnemonic(_, s) => write!(f, "Unknown mnemonic {}", s),
+            Self::UnknownRegister(_, s) => write!(f, "Unknown register {}", s),
+            Self::InvalidImmediate(_, s) => write!(f, "Invalid immediate {}", s),
+            Self::InvalidLabel(_, s) => write!(f, "Invalid label {}", s),
+            Self::WrongOperandCount(_, s) => write!(f, "Wrong number of operands for {}", s),
+        }
+    }
+}
+
+impl Display for AssembleErr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UndefinedLabel(l) => write!(f, "Undefined label {}", l),
+            Self::DuplicateLabel(l) => write!(f, "Label {} is defined more than once", l),
+            Self::ProgramTooLarge => write!(f, "Program does not fit in program memory"),
+        }
+    }
+}
+
+impl Display for DisassembleErr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::IllegalOpcode(address, op) => write!(f, "Illegal opcode {:#04x} at {:#06x}", op, address),
+            Self::Truncated(address) => write!(f, "Instruction at {:#06x} is missing operand bytes", address),
+        }
+    }
+}
+
+impl Display for EmulatorErr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::IllegalOpcode(address, op) => write!(f, "Illegal opcode {:#04x} at {:#06x}", op, address),
+            Self::PcOutOfBounds(pc) => write!(f, "Program counter {:#06x} is out of bounds", pc),
+            Self::StepLimit(steps) => write!(f, "Step limit of {} reached", steps),
+        }
+    }
+}
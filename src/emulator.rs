@@ -0,0 +1,344 @@
+//! Emulator for the instruction set in `cgen/instruction.rs`.
+//!
+//! Program memory holds the binary image (see `cgen/assembler.rs`), data
+//! memory is a separate 64 KiB address space reached through MAR.
+//!
+//! Semantics, A being the accumulator:
+//! - add/adc/sub/sbc REG: A = A +/- REG (+/- carry), carry is set on carry out
+//!   or borrow
+//! - ror REG: A = REG >> 1 with the carry rotated into bit 7, bit 0 goes to
+//!   the carry
+//! - nor/and REG: A = !(A | REG) / A & REG
+//! - rmov REG: A = REG, amov REG: REG = A
+//! - zac: A = 0, ima n: A = n, imr REG n: REG = n
+//! - str REG: mem[MAR] = REG, ld REG: REG = mem[MAR]
+//! - push REG: mem[SP] = REG, pop REG: REG = mem[SP], SP is left as is
+//! - in REG / out REG: read / write the port numbered MARL
+//! - spc: PC = MAR
+//! - jmp, bca/bnc (carry), bze/bnz (zero), bsi (sign), bpa (even parity)
+//!
+//! Every instruction that writes A updates the zero, sign and parity flags,
+//! only add/adc/sub/sbc/ror/stc/clc touch the carry.
+
+use std::collections::{HashMap, VecDeque};
+use std::fmt::Display;
+
+use crate::cgen::assembler::*;
+use crate::cgen::instruction::*;
+
+pub const MEMORY_SIZE: usize = 0x10000;
+pub const DEFAULT_MAX_STEPS: u64 = 50_000_000;
+
+pub struct Emulator {
+    pub program: Vec<u8>,
+    pub memory: Vec<u8>,
+    pub regs: [u8; 8],
+    pub acc: u8,
+    pub carry: bool,
+    pub zero: bool,
+    pub sign: bool,
+    pub parity: bool,
+    pub pc: u16,
+    pub halted: bool,
+    pub steps: u64,
+    pub input: HashMap<u8, VecDeque<u8>>,
+    pub output: Vec<(u8, u8)>, //(PORT, VALUE)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum EmulatorErr {
+    IllegalOpcode(u16, u8), //(ADDRESS, OPCODE)
+    PcOutOfBounds(u16),
+    StepLimit(u64),
+}
+
+impl Emulator {
+    pub fn new(program: Vec<u8>) -> Self {
+        Emulator {
+            program,
+            memory: vec![0; MEMORY_SIZE],
+            regs: [0; 8],
+            acc: 0,
+            carry: false,
+            zero: false,
+            sign: false,
+            parity: false,
+            pc: 0,
+            halted: false,
+            steps: 0,
+            input: HashMap::new(),
+            output: vec![],
+        }
+    }
+
+    pub fn from_commands(commands: &[AssemblyCommand]) -> Result<Self, AssembleErr> {
+        Ok(Emulator::new(assemble(commands)?))
+    }
+
+    ///Queues bytes to be read by `in` from `port`
+    pub fn feed_input(&mut self, port: u8, bytes: &[u8]) {
+        self.input.entry(port).or_default().extend(bytes.iter());
+    }
+
+    pub fn sp(&self) -> u16 {
+        u16::from_le_bytes([self.regs[SPL as usize], self.regs[SPH as usize]])
+    }
+
+    pub fn bp(&self) -> u16 {
+        u16::from_le_bytes([self.regs[BPL as usize], self.regs[BPH as usize]])
+    }
+
+    pub fn mar(&self) -> u16 {
+        u16::from_le_bytes([self.regs[MARL as usize], self.regs[MARH as usize]])
+    }
+
+    ///Runs until `hlt`, stops with an error after `max_steps` instructions
+    pub fn run(&mut self, max_steps: u64) -> Result<(), EmulatorErr> {
+        while !self.halted {
+            if self.steps >= max_steps {
+                return Err(EmulatorErr::StepLimit(self.steps))
+            }
+
+            self.step()?;
+        }
+
+        Ok(())
+    }
+
+    pub fn step(&mut self) -> Result<(), EmulatorErr> {
+        let address = self.pc;
+        let opcode = self.fetch()?;
+        self.steps += 1;
+
+        if opcode < REG_OPS_END {
+            let reg = (opcode & 0b111) as usize;
+
+            match opcode & !0b111 {
+                OP_ADD => self.acc = self.add(self.acc, self.regs[reg], false),
+                OP_SUB => self.acc = self.sub(self.acc, self.regs[reg], false),
+                OP_ADC => self.acc = self.add(self.acc, self.regs[reg], self.carry),
+                OP_SBC => self.acc = self.sub(self.acc, self.regs[reg], self.carry),
+
+                OP_ROR => {
+                    let value = self.regs[reg];
+                    self.acc = (value >> 1) | ((self.carry as u8) << 7);
+                    self.carry = value & 1 == 1;
+                }
+
+                OP_NOR => self.acc = !(self.acc | self.regs[reg]),
+                OP_AND => self.acc &= self.regs[reg],
+                OP_RMOV => self.acc = self.regs[reg],
+                OP_AMOV => self.regs[reg] = self.acc,
+
+                OP_STR => {
+                    let mar = self.mar() as usize;
+                    self.memory[mar] = self.regs[reg];
+                }
+
+                OP_LD => self.regs[reg] = self.memory[self.mar() as usize],
+
+                OP_IN => {
+                    let port = self.regs[MARL as usize];
+                    self.regs[reg] = self.input.get_mut(&port).and_then(|q| q.pop_front()).unwrap_or(0);
+                }
+
+                OP_OUT => self.output.push((self.regs[MARL as usize], self.regs[reg])),
+
+                OP_PUSH => {
+                    let sp = self.sp() as usize;
+                    self.memory[sp] = self.regs[reg];
+                }
+
+                OP_POP => self.regs[reg] = self.memory[self.sp() as usize],
+
+                OP_IMR => self.regs[reg] = self.fetch()?,
+
+                _ => unreachable!()
+            }
+
+            if matches!(opcode & !0b111, OP_ADD | OP_SUB | OP_ADC | OP_SBC | OP_ROR | OP_NOR | OP_AND | OP_RMOV) {
+                self.set_flags();
+            }
+
+            return Ok(())
+        }
+
+        match opcode {
+            OP_STC => self.carry = true,
+            OP_CLC => self.carry = false,
+
+            OP_ZAC => {
+                self.acc = 0;
+                self.set_flags();
+            }
+
+            OP_SPC => self.pc = self.mar(),
+            OP_NOP => {}
+            OP_HLT => self.halted = true,
+
+            OP_IMA => {
+                self.acc = self.fetch()?;
+                self.set_flags();
+            }
+
+            OP_JMP | OP_BCA | OP_BNC | OP_BZE | OP_BNZ | OP_BSI | OP_BPA => {
+                let target = u16::from_le_bytes([self.fetch()?, self.fetch()?]);
+
+                let taken = match opcode {
+                    OP_JMP => true,
+                    OP_BCA => self.carry,
+                    OP_BNC => !self.carry,
+                    OP_BZE => self.zero,
+                    OP_BNZ => !self.zero,
+                    OP_BSI => self.sign,
+                    OP_BPA => self.parity,
+                    _ => unreachable!()
+                };
+
+                if taken {
+                    self.pc = target;
+                }
+            }
+
+            _ => return Err(EmulatorErr::IllegalOpcode(address, opcode))
+        }
+
+        Ok(())
+    }
+
+    fn fetch(&mut self) -> Result<u8, EmulatorErr> {
+        match self.program.get(self.pc as usize) {
+            Some(t) => {
+                self.pc = self.pc.wrapping_add(1);
+                Ok(*t)
+            }
+
+            None => Err(EmulatorErr::PcOutOfBounds(self.pc))
+        }
+    }
+
+    fn add(&mut self, x: u8, y: u8, carry_in: bool) -> u8 {
+        let sum = x as u16 + y as u16 + carry_in as u16;
+        self.carry = sum > 0xff;
+        sum as u8
+    }
+
+    fn sub(&mut self, x: u8, y: u8, borrow_in: bool) -> u8 {
+        let subtrahend = y as u16 + borrow_in as u16;
+        self.carry = (x as u16) < subtrahend;
+        (x as u16).wrapping_sub(subtrahend) as u8
+    }
+
+    fn set_flags(&mut self) {
+        self.zero = self.acc == 0;
+        self.sign = self.acc & 0x80 != 0;
+        self.parity = self.acc.count_ones().is_multiple_of(2);
+    }
+}
+
+impl Display for Emulator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "PC: {:#06x} Steps: {} Halted: {}", self.pc, self.steps, self.halted)?;
+
+        write!(f, "A: {:#04x}", self.acc)?;
+        for (n, name) in REG_NAMES.iter().enumerate() {
+            write!(f, " {}: {:#04x}", name, self.regs[n])?;
+        }
+        writeln!(f)?;
+
+        writeln!(f, "BP: {:#06x} SP: {:#06x} MAR: {:#06x}", self.bp(), self.sp(), self.mar())?;
+        writeln!(f, "Carry: {} Zero: {} Sign: {} Parity: {}",
+            self.carry as u8, self.zero as u8, self.sign as u8, self.parity as u8)?;
+
+        for (port, value) in &self.output {
+            writeln!(f, "Out Port {}: {}", port, value)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(program: Vec<Instruction>) -> Emulator {
+        let commands = program.into_iter().map(AssemblyCommand::Instruction).collect::<Vec<AssemblyCommand>>();
+        let mut emu = Emulator::from_commands(&commands).unwrap();
+        emu.run(1000).unwrap();
+        emu
+    }
+
+    #[test]
+    fn test_arithmetic_flags() {
+        let emu = run(vec![
+            Instruction::Imr(R0, 200),
+            Instruction::Imr(R1, 100),
+            Instruction::Rmov(R0),
+            Instruction::Add(R1),
+            Instruction::Hlt,
+        ]);
+        assert_eq!(emu.acc, 44);
+        assert!(emu.carry);
+
+        let emu = run(vec![
+            Instruction::Imr(R0, 5),
+            Instruction::Ima(5),
+            Instruction::Sub(R0),
+            Instruction::Hlt,
+        ]);
+        assert!(emu.zero && !emu.carry && emu.parity);
+
+        let emu = run(vec![
+            Instruction::Stc,
+            Instruction::Imr(R0, 0b10),
+            Instruction::Ror(R0),
+            Instruction::Hlt,
+        ]);
+        assert_eq!(emu.acc, 0b1000_0001);
+        assert!(!emu.carry && emu.sign);
+    }
+
+    #[test]
+    fn test_memory_and_ports() {
+        let emu = run(vec![
+            Instruction::Imr(MARL, 0x34),
+            Instruction::Imr(MARH, 0x12),
+            Instruction::Imr(R0, 7),
+            Instruction::Str(R0),
+            Instruction::Ld(R1),
+            Instruction::Imr(SPL, 0x10),
+            Instruction::Push(R1),
+            Instruction::Out(R1),
+            Instruction::Hlt,
+        ]);
+        assert_eq!(emu.memory[0x1234], 7);
+        assert_eq!(emu.memory[0x10], 7);
+        assert_eq!(emu.output, vec![(0x34, 7)]);
+    }
+
+    #[test]
+    fn test_branches() {
+        let commands = vec![
+            AssemblyCommand::Instruction(Instruction::Imr(R0, 3)),
+            AssemblyCommand::Instruction(Instruction::Imr(R1, 1)),
+            AssemblyCommand::Label("loop".to_string()),
+            AssemblyCommand::Instruction(Instruction::Rmov(R0)),
+            AssemblyCommand::Instruction(Instruction::Sub(R1)),
+            AssemblyCommand::Instruction(Instruction::Amov(R0)),
+            AssemblyCommand::Instruction(Instruction::Bnz("loop".to_string())),
+            AssemblyCommand::Instruction(Instruction::Hlt),
+        ];
+
+        let mut emu = Emulator::from_commands(&commands).unwrap();
+        emu.run(100).unwrap();
+        assert_eq!(emu.regs[R0 as usize], 0);
+        assert_eq!(emu.steps, 2 + 3 * 4 + 1);
+
+        let mut emu = Emulator::new(vec![OP_JMP, 0, 0]);
+        assert_eq!(emu.run(10), Err(EmulatorErr::StepLimit(10)));
+
+        let mut emu = Emulator::new(vec![0xff]);
+        assert_eq!(emu.run(10), Err(EmulatorErr::IllegalOpcode(0, 0xff)));
+    }
+}
@@ -1,4 +1,5 @@
-use crate::stmt::TypeDeclr;
+use crate::lexer::escape_value;
+use crate::stmt::{Stmt, TypeDeclr};
 use crate::token::*;
 use crate::types::ValueType;
 
@@ -7,13 +8,13 @@ macro_rules! try_into_all_types {
     ($self:ident, $t:ident) => {
         match $t {
             ValueType::U8 => $self.try_into().ok().map(|x| NumLiteral::U8(x)),
-            ValueType::I8 => $self.try_into().ok().map(|x| NumLiteral::U8(x)),
+            ValueType::I8 => $self.try_into().ok().map(|x| NumLiteral::I8(x)),
             ValueType::U16 => $self.try_into().ok().map(|x| NumLiteral::U16(x)),
             ValueType::I16 => $self.try_into().ok().map(|x| NumLiteral::I16(x)),
             ValueType::U32 => $self.try_into().ok().map(|x| NumLiteral::U32(x)),
             ValueType::I32 => $self.try_into().ok().map(|x| NumLiteral::I32(x)),
-            ValueType::U64 => $self.try_into().ok().map(|x| NumLiteral::I64(x)),
-            ValueType::I64 => $self.try_into().ok().map(|x| NumLiteral::U64(x)),
+            ValueType::U64 => $self.try_into().ok().map(|x| NumLiteral::U64(x)),
+            ValueType::I64 => $self.try_into().ok().map(|x| NumLiteral::I64(x)),
             _ => None,
         }
     };
@@ -22,13 +23,17 @@ macro_rules! try_into_all_types {
 #[derive(Debug, Clone)]
 pub enum Expr {
     Assign(Box<BinaryExpr>),
+    CompoundAssign(Box<BinaryExpr>), //the operator includes the '='
     Equality(Box<BinaryExpr>),
     Comparison(Box<BinaryExpr>),
     Term(Box<BinaryExpr>),
-    Shift(Box<Expr>, Lexeme, NumLiteral),
+    Factor(Box<BinaryExpr>),
+    Shift(Box<BinaryExpr>),
     Unary(Lexeme, Box<Expr>),
     Cast(Box<Expr>, Lexeme, TypeDeclr),
     FnCall(Lexeme, Args),
+    If(Lexeme, Box<(Expr, Expr, Expr)>), //(IF KEYWORD, (CONDITION, TRUE VALUE, FALSE VALUE))
+    Loop(Lexeme, Option<Lexeme>, Box<Stmt>), //(LOOP KEYWORD, LABEL, BODY), the value comes from its breaks
     Primary(Box<PrimaryExpr>),
 }
 
@@ -36,6 +41,10 @@ pub enum Expr {
 pub enum PrimaryExpr {
     Grouping(Expr),
     NumLiteral(NumLiteral, Lexeme),
+    StrLiteral(Vec<u8>, Lexeme),
+    StructLiteral(Lexeme, Vec<(Lexeme, Expr)>), //(STRUCT NAME, (FIELD, VALUE))
+    ArrayLiteral(Lexeme, Vec<Expr>), //the lexeme is the opening bracket
+    ArrayRepeat(Lexeme, Expr, Expr), //[VALUE; COUNT]
     Variable(Variable),
     EnumVariant(Lexeme, Lexeme),
     Ref(Lexeme, Variable),
@@ -60,7 +69,130 @@ pub enum NumLiteral {
     I64(i64),
 }
 
+#[derive(Debug, Clone, PartialEq)]
+pub enum LitErr {
+    Malformed,
+    OutOfRange,
+}
+
+pub fn suffix_type(suffix: &str) -> Option<ValueType> {
+    match suffix {
+        "u8" => Some(ValueType::U8),
+        "i8" => Some(ValueType::I8),
+        "u16" => Some(ValueType::U16),
+        "i16" => Some(ValueType::I16),
+        "u32" => Some(ValueType::U32),
+        "i32" => Some(ValueType::I32),
+        "u64" => Some(ValueType::U64),
+        "i64" => Some(ValueType::I64),
+        _ => None
+    }
+}
+
+///Value and type suffix of the text of a Lit token:
+///decimal, 0x hex, 0b binary or 0o octal digits with optional underscores and suffix,
+///or a character like 'A' or '\n'
+pub fn split_lit(text: &str) -> Result<(u64, Option<ValueType>), LitErr> {
+    if let Some(quoted) = text.strip_prefix('\'') {
+        let bytes = quoted.as_bytes();
+
+        let (value, len) = match bytes.first() {
+            Some(b'\\') => escape_value(&bytes[1..]).map(|(v, n)| (v, n + 1)).ok_or(LitErr::Malformed)?,
+            Some(b'\'') | None => return Err(LitErr::Malformed),
+            Some(c) => (*c, 1),
+        };
+
+        if &quoted[len..] != "'" {
+            return Err(LitErr::Malformed)
+        }
+
+        return Ok((value as u64, None))
+    }
+
+    let (radix, body) = match text.get(..2) {
+        Some("0x") => (16, &text[2..]),
+        Some("0b") => (2, &text[2..]),
+        Some("0o") => (8, &text[2..]),
+        _ => (10, text),
+    };
+
+    //'u' and 'i' are not digits in any radix
+    let (digits, suffix) = match body.find(['u', 'i']) {
+        Some(n) => (&body[..n], Some(suffix_type(&body[n..]).ok_or(LitErr::Malformed)?)),
+        None => (body, None),
+    };
+
+    let digits = digits.replace('_', "");
+    if digits.is_empty() || !digits.chars().all(|c| c.is_digit(radix)) {
+        return Err(LitErr::Malformed)
+    }
+
+    match u64::from_str_radix(&digits, radix) {
+        Ok(value) => Ok((value, suffix)),
+        Err(_) => Err(LitErr::OutOfRange)
+    }
+}
+
 impl NumLiteral {
+    ///A suffix sets the variant, otherwise the smallest unsigned type that fits is picked
+    ///and negative literals follow `negate`
+    pub fn from_lit(text: &str, negative: bool) -> Result<NumLiteral, LitErr> {
+        let (value, suffix) = split_lit(text)?;
+
+        let suffix = match suffix {
+            Some(t) => t,
+
+            None => {
+                let num = if value <= u8::MAX as u64 {
+                    NumLiteral::U8(value as u8)
+                } else if value <= u16::MAX as u64 {
+                    NumLiteral::U16(value as u16)
+                } else if value <= u32::MAX as u64 {
+                    NumLiteral::U32(value as u32)
+                } else {
+                    NumLiteral::U64(value)
+                };
+
+                if !negative {
+                    return Ok(num)
+                }
+
+                return num.negate().map_err(|_| LitErr::OutOfRange)
+            }
+        };
+
+        //the magnitude of a negative value can be one bigger than i64::MAX
+        let signed: i128 = if negative { -(value as i128) } else { value as i128 };
+
+        let num = match suffix {
+            ValueType::U8 => signed.try_into().ok().map(NumLiteral::U8),
+            ValueType::I8 => signed.try_into().ok().map(NumLiteral::I8),
+            ValueType::U16 => signed.try_into().ok().map(NumLiteral::U16),
+            ValueType::I16 => signed.try_into().ok().map(NumLiteral::I16),
+            ValueType::U32 => signed.try_into().ok().map(NumLiteral::U32),
+            ValueType::I32 => signed.try_into().ok().map(NumLiteral::I32),
+            ValueType::U64 => signed.try_into().ok().map(NumLiteral::U64),
+            ValueType::I64 => signed.try_into().ok().map(NumLiteral::I64),
+            _ => unreachable!()
+        };
+
+        num.ok_or(LitErr::OutOfRange)
+    }
+
+    ///every variant fits an i128 exactly
+    pub fn value(&self) -> i128 {
+        match *self {
+            Self::U8(x) => x as i128,
+            Self::I8(x) => x as i128,
+            Self::U16(x) => x as i128,
+            Self::I16(x) => x as i128,
+            Self::U32(x) => x as i128,
+            Self::I32(x) => x as i128,
+            Self::U64(x) => x as i128,
+            Self::I64(x) => x as i128,
+        }
+    }
+
     pub fn try_implicit_cast(&self, ty: &ValueType) -> Option<NumLiteral> {
         match *self {
             Self::U8(x) => try_into_all_types!(x, ty),
@@ -74,6 +206,44 @@ impl NumLiteral {
         }
     }
 
+    ///`value` wrapped around to the width of `ty` like a cast at runtime,
+    ///pointers are u16
+    pub fn wrapping_from(value: i128, ty: &ValueType) -> Option<NumLiteral> {
+        let ty = match ty {
+            ValueType::Pointer(_) => &ValueType::U16,
+            _ => ty,
+        };
+
+        let bits: u32 = match ty {
+            ValueType::U8 | ValueType::I8 => 8,
+            ValueType::U16 | ValueType::I16 => 16,
+            ValueType::U32 | ValueType::I32 => 32,
+            ValueType::U64 | ValueType::I64 => 64,
+            _ => return None,
+        };
+
+        let unsigned = (value as u128) & ((1u128 << bits) - 1);
+
+        let value = match ty.is_signed_type() {
+            true => ((unsigned << (128 - bits)) as i128) >> (128 - bits),
+            false => unsigned as i128,
+        };
+
+        try_into_all_types!(value, ty)
+    }
+
+    ///how far shifting a value of `bits` bits by this amount moves it,
+    ///the amount is read as unsigned and shifting by `bits` or more leaves only 0s or sign bits
+    pub fn shift_count(&self, bits: u8) -> u8 {
+        let overflows = (1..self.size()).any(|nth| self.get_nth_byte(nth) != 0);
+
+        match overflows {
+            true => bits,
+            false => self.get_nth_byte(0).min(bits),
+        }
+    }
+
+    #[allow(clippy::needless_return, clippy::neg_multiply, clippy::result_unit_err)]
     pub fn negate(&self) -> Result<NumLiteral, ()> {
         match *self {
             Self::U8(n) => {
@@ -119,6 +289,7 @@ pub enum Variable {
     Id(Lexeme),
     StructField(Box<(Variable, Variable)>),
     Array(Box<Variable>, Expr),
+    Deref(Lexeme, Box<Expr>), //(OPERATOR, POINTER), what the pointer points to
 }
 
 #[derive(Debug, Clone)]
@@ -147,6 +318,8 @@ impl Variable {
                 s.1.neg_unary_literals()?;
             }
 
+            Variable::Deref(_, pointer) => pointer.neg_unary_literals()?,
+
             Variable::Id(_) => {}
         }
 
@@ -158,6 +331,7 @@ impl Variable {
             Variable::Array(n, _) => n.get_first_lexeme(),
             Variable::Id(id) => id.clone(),
             Variable::StructField(s) => s.0.get_first_lexeme(),
+            Variable::Deref(op, _) => op.clone(),
         }
     }
 }
@@ -183,7 +357,7 @@ impl Expr {
                 }
             }
 
-            Expr::Assign(a) => {
+            Expr::Assign(a) | Expr::CompoundAssign(a) => {
                 a.left.neg_unary_literals()?;
                 a.right.neg_unary_literals()?;
             }
@@ -205,6 +379,23 @@ impl Expr {
             Expr::Primary(p) => match &mut **p {
                 PrimaryExpr::Grouping(g) => g.neg_unary_literals()?,
 
+                PrimaryExpr::StructLiteral(_, fields) => {
+                    for (_, value) in fields.iter_mut() {
+                        value.neg_unary_literals()?;
+                    }
+                }
+
+                PrimaryExpr::ArrayLiteral(_, items) => {
+                    for item in items.iter_mut() {
+                        item.neg_unary_literals()?;
+                    }
+                }
+
+                PrimaryExpr::ArrayRepeat(_, value, count) => {
+                    value.neg_unary_literals()?;
+                    count.neg_unary_literals()?;
+                }
+
                 PrimaryExpr::Variable(v) => v.neg_unary_literals()?,
 
                 PrimaryExpr::Ref(_, v) => {
@@ -214,9 +405,19 @@ impl Expr {
                 _ => {}
             },
 
-            Expr::Shift(v, _, _) => v.neg_unary_literals()?,
+            Expr::Loop(_, _, body) => body.neg_unary_literals()?,
+
+            Expr::If(_, branches) => {
+                branches.0.neg_unary_literals()?;
+                branches.1.neg_unary_literals()?;
+                branches.2.neg_unary_literals()?;
+            }
+
+            Expr::Shift(b) => b.neg_unary_literals()?,
 
             Expr::Term(b) => b.neg_unary_literals()?,
+
+            Expr::Factor(b) => b.neg_unary_literals()?,
         }
 
         Ok(())
@@ -224,7 +425,7 @@ impl Expr {
 
     pub fn get_first_lexeme(&self) -> Lexeme {
         match self {
-            Expr::Assign(a) => a.left.get_first_lexeme(),
+            Expr::Assign(a) | Expr::CompoundAssign(a) => a.left.get_first_lexeme(),
 
             Expr::Cast(v, _, _) => v.get_first_lexeme(),
 
@@ -232,14 +433,20 @@ impl Expr {
 
             Expr::Equality(binary) => binary.left.get_first_lexeme(),
 
-            Expr::Shift(v, _, _) => v.get_first_lexeme(),
+            Expr::Shift(b) => b.left.get_first_lexeme(),
 
             Expr::Term(b) => b.left.get_first_lexeme(),
 
+            Expr::Factor(b) => b.left.get_first_lexeme(),
+
             Expr::Unary(left, _) => left.clone(),
 
             Expr::FnCall(name, _) => name.clone(),
 
+            Expr::If(key, _) => key.clone(),
+
+            Expr::Loop(key, label, _) => label.clone().unwrap_or(key.clone()),
+
             Expr::Primary(p) => match &**p {
                 PrimaryExpr::EnumVariant(n, _) => n.clone(),
 
@@ -247,6 +454,12 @@ impl Expr {
 
                 PrimaryExpr::NumLiteral(_, l) => l.clone(),
 
+                PrimaryExpr::StrLiteral(_, l) => l.clone(),
+
+                PrimaryExpr::StructLiteral(name, _) => name.clone(),
+
+                PrimaryExpr::ArrayLiteral(bracket, _) | PrimaryExpr::ArrayRepeat(bracket, _, _) => bracket.clone(),
+
                 PrimaryExpr::Ref(op, _) => op.clone(),
 
                 PrimaryExpr::Variable(v) => v.get_first_lexeme(),
@@ -257,7 +470,7 @@ impl Expr {
 
 #[cfg(test)]
 mod tests {
-    use super::NumLiteral;
+    use super::{LitErr, NumLiteral};
     use super::ValueType;
 
     #[test]
@@ -283,4 +496,47 @@ mod tests {
             Some(NumLiteral::U16(5))
         );
     }
+
+    #[test]
+    fn test_wrapping_from() {
+        assert_eq!(NumLiteral::wrapping_from(300, &ValueType::U8), Some(NumLiteral::U8(44)));
+        assert_eq!(NumLiteral::wrapping_from(-1, &ValueType::U16), Some(NumLiteral::U16(0xffff)));
+        assert_eq!(NumLiteral::wrapping_from(0xff80, &ValueType::I8), Some(NumLiteral::I8(-128)));
+        assert_eq!(NumLiteral::wrapping_from(-1, &ValueType::U64), Some(NumLiteral::U64(u64::MAX)));
+        assert_eq!(NumLiteral::wrapping_from(0x1_4000, &ValueType::Pointer(Box::new(ValueType::U8))), Some(NumLiteral::U16(0x4000)));
+        assert_eq!(NumLiteral::wrapping_from(1, &ValueType::Void), None);
+    }
+
+    #[test]
+    fn test_shift_count() {
+        assert_eq!(NumLiteral::U8(3).shift_count(16), 3);
+        assert_eq!(NumLiteral::U8(20).shift_count(16), 16);
+        assert_eq!(NumLiteral::U16(0x100).shift_count(8), 8);
+        assert_eq!(NumLiteral::I8(-1).shift_count(32), 32);
+    }
+
+    #[test]
+    fn test_from_lit() {
+        assert_eq!(NumLiteral::from_lit("0xFF", false), Ok(NumLiteral::U8(255)));
+        assert_eq!(NumLiteral::from_lit("0b1010_0001", false), Ok(NumLiteral::U8(0b1010_0001)));
+        assert_eq!(NumLiteral::from_lit("0o17", false), Ok(NumLiteral::U8(15)));
+        assert_eq!(NumLiteral::from_lit("1_000", false), Ok(NumLiteral::U16(1000)));
+        assert_eq!(NumLiteral::from_lit("'A'", false), Ok(NumLiteral::U8(65)));
+        assert_eq!(NumLiteral::from_lit("'\\n'", false), Ok(NumLiteral::U8(10)));
+        assert_eq!(NumLiteral::from_lit("300u16", false), Ok(NumLiteral::U16(300)));
+        assert_eq!(NumLiteral::from_lit("5i8", false), Ok(NumLiteral::I8(5)));
+        assert_eq!(NumLiteral::from_lit("0xFF_u32", false), Ok(NumLiteral::U32(255)));
+        assert_eq!(NumLiteral::from_lit("128i8", true), Ok(NumLiteral::I8(-128)));
+        assert_eq!(NumLiteral::from_lit("9223372036854775808i64", true), Ok(NumLiteral::I64(i64::MIN)));
+        assert_eq!(NumLiteral::from_lit("5", true), Ok(NumLiteral::I8(-5)));
+
+        assert_eq!(NumLiteral::from_lit("128i8", false), Err(LitErr::OutOfRange));
+        assert_eq!(NumLiteral::from_lit("256u8", false), Err(LitErr::OutOfRange));
+        assert_eq!(NumLiteral::from_lit("5u8", true), Err(LitErr::OutOfRange));
+        assert_eq!(NumLiteral::from_lit("0x1_0000_0000_0000_0000", false), Err(LitErr::OutOfRange));
+        assert_eq!(NumLiteral::from_lit("5u7", false), Err(LitErr::Malformed));
+        assert_eq!(NumLiteral::from_lit("0b102", false), Err(LitErr::Malformed));
+        assert_eq!(NumLiteral::from_lit("0x", false), Err(LitErr::Malformed));
+        assert_eq!(NumLiteral::from_lit("''", false), Err(LitErr::Malformed));
+    }
 }
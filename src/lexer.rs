@@ -1,146 +1,230 @@
+use crate::expr::{split_lit, LitErr};
 use crate::token::*;
-use fancy_regex::Regex;
+
+pub const KEYWORDS: [&str; 15] = [
+    "let", "static", "const", "if", "fn", "else", "while", "loop", "for", "in", "return", "continue", "struct", "enum", "break",
+];
+
+///words that are lexed as operators rather than identifiers
+pub const WORD_OPS: [&str; 1] = ["as"];
+
+///longest symbol first, so "<<=" wins over "<<" and "<"
+const MAX_SYMBOL_LEN: usize = 3;
+
+///the position of the error is left in `Lexer::line` and `Lexer::col`
+#[derive(Debug, Clone, PartialEq)]
+pub enum LexErr {
+    UnknownToken,
+    UnterminatedComment,
+    MalformedLiteral,
+    UnterminatedString,
+}
 
 #[derive(Debug)]
 pub struct Lexer {
-    data: String,
+    data: Vec<u8>,
     ptr: usize,
     pub line: usize,
     pub col: usize,
 }
 
 impl Lexer {
+    #[allow(clippy::result_unit_err)]
     pub fn new(text: String) -> Result<Self, ()> {
         if !text.is_ascii() {
             return Err(())
         }
 
         Ok(Lexer {
-            data: text,
+            data: text.into_bytes(),
             ptr: 0,
             line: 1,
             col: 1 })
     }
 
-    pub fn lex(&mut self) -> Result<Vec<Lexeme>, ()> {
-        let reg_key = Regex::new(r"(?x)
-        ^let |
-        ^if |
-        ^fn |
-        ^else |
-        ^while |
-        ^loop |
-        ^for |
-        ^return |
-        ^continue |
-        ^struct |
-        ^enum |
-        ^break").unwrap();
-
-        let reg_op = Regex::new(r"(?x)
-        ^ as(?=\s) |
-        ^ \= (?!\=) |
-        ^ \+ |
-        ^ \- |
-        ^ \& (?!\&)|
-        ^ \* |
-        ^ << |
-        ^ >> |
-        ^ \|(?!\|) | #single pipe
-        ^ \! (?!\=)|
-        ^ \~\|").unwrap();
-        
-        let reg_cond = Regex::new(r"(?x)
-        ^ \|\| |
-        ^ \&\& |
-        ^ <\= |
-        ^ >= |
-        ^ < (?!<) |
-        ^ > (?!>) |
-        ^ \!\= |
-        ^ \=\=").unwrap();
-
-        let reg_id = Regex::new(r"^[_[[:alpha:]]][_@[[:alnum:]]]*").unwrap();
-        
-        let reg_lit = Regex::new(r"^-?\d+(?![[:alpha:]])").unwrap();
-
-        let reg_arrow = Regex::new(r"^\->").unwrap();
-
+    pub fn lex(&mut self) -> Result<Vec<Lexeme>, LexErr> {
         let mut lexeme_list: Vec<Lexeme> = vec![];
 
-        while self.current() != 0 {
+        loop {
             self.skip_whitespace();
 
-            if let Some(m) = reg_arrow.find(&self.data.as_str()[self.ptr..]).unwrap() {
-                lexeme_list.push(Lexeme::new(self.line, self.col, Token::Arrow));
-                self.advance(m.as_str().len());
-                continue;
+            if self.starts_with("/*") {
+                self.skip_block_comment()?;
+                continue
             }
 
-            if let Some(m) = reg_key.find(&self.data.as_str()[self.ptr..]).unwrap() {
-                lexeme_list.push(Lexeme::new(self.line, self.col, Token::Key(m.as_str().to_string())));
-                self.advance(m.as_str().len());
-                continue;
-            }
+            if self.starts_with("//") {
+                let (line, col) = (self.line, self.col);
+                let comment = self.take_while(|x| x != b'\n');
 
-            if let Some(m) = reg_op.find(&self.data.as_str()[self.ptr..]).unwrap() {
-                lexeme_list.push(Lexeme::new(self.line, self.col, Token::Op(m.as_str().to_string())));
-                self.advance(m.as_str().len());
-                continue;
+                //"////" and longer are plain comments, like in Rust
+                if let Some(doc) = comment.strip_prefix("///") {
+                    if !doc.starts_with('/') {
+                        lexeme_list.push(Lexeme::new(line, col, Token::DocComment(doc.trim().to_string())));
+                    }
+                }
+
+                continue
             }
 
-            if let Some(m) = reg_cond.find(&self.data.as_str()[self.ptr..]).unwrap() {
-                lexeme_list.push(Lexeme::new(self.line, self.col, Token::Cond(m.as_str().to_string())));
-                self.advance(m.as_str().len());
-                continue;
+            let c = match self.current() {
+                Some(t) => t,
+                None => break
+            };
+
+            let (line, col) = (self.line, self.col);
+
+            if self.starts_with("\"") || self.starts_with("b\"") {
+                let len = match self.str_literal_len() {
+                    Some(t) => t,
+                    None => return Err(LexErr::UnterminatedString)
+                };
+
+                let lit = String::from_utf8(self.data[self.ptr..self.ptr + len].to_vec()).unwrap();
+
+                lexeme_list.push(Lexeme::new(line, col, Token::Str(lit)));
+                self.advance(len);
+                continue
             }
 
-            if let Some(m) = reg_id.find(&self.data.as_str()[self.ptr..]).unwrap() {
-                lexeme_list.push(Lexeme::new(self.line, self.col, Token::Id(m.as_str().to_string())));
-                self.advance(m.as_str().len());
-                continue;
+            if c == b'_' || c.is_ascii_alphabetic() {
+                let word = self.take_while(|x| x == b'_' || x == b'@' || x.is_ascii_alphanumeric());
+                lexeme_list.push(Lexeme::new(line, col, classify_word(word)));
+                continue
             }
 
-            if let Some(m) = reg_lit.find(&self.data.as_str()[self.ptr..]).unwrap() {
-                lexeme_list.push(Lexeme::new(self.line, self.col, Token::Lit(m.as_str().to_string())));
-                self.advance(m.as_str().len());
-                continue;
+            if c.is_ascii_digit() {
+                let lit = self.take_while(|x| x == b'_' || x.is_ascii_alphanumeric());
+
+                //values too big for their type are reported by the parser
+                if split_lit(&lit) == Err(LitErr::Malformed) {
+                    self.line = line;
+                    self.col = col;
+                    return Err(LexErr::MalformedLiteral)
+                }
+
+                lexeme_list.push(Lexeme::new(line, col, Token::Lit(lit)));
+                continue
             }
 
-            let mut no_match = false;
-
-            match self.current() {
-                b'(' => lexeme_list.push(Lexeme::new(self.line, self.col, Token::ParenOpen)),
-                b')' => lexeme_list.push(Lexeme::new(self.line, self.col, Token::ParenClose)),
-                b'[' => lexeme_list.push(Lexeme::new(self.line, self.col, Token::SquareOpen)),
-                b']' => lexeme_list.push(Lexeme::new(self.line, self.col, Token::SquareClose)),
-                b'{' => lexeme_list.push(Lexeme::new(self.line, self.col, Token::CurlyOpen)),
-                b'}' => lexeme_list.push(Lexeme::new(self.line, self.col, Token::CurlyClose)),
-                b';' => lexeme_list.push(Lexeme::new(self.line, self.col, Token::SemiCol)),
-                b':' => lexeme_list.push(Lexeme::new(self.line, self.col, Token::Col)),
-                b',' => lexeme_list.push(Lexeme::new(self.line, self.col, Token::Comma)),
-                b'.' => lexeme_list.push(Lexeme::new(self.line, self.col, Token::Period)),
-                _ => no_match = true
+            //a quote that does not start a character literal starts a loop label
+            if c == b'\'' && self.char_literal_len().is_none() && self.is_label_start() {
+                self.advance(1);
+                let name = self.take_while(|x| x == b'_' || x.is_ascii_alphanumeric());
+
+                lexeme_list.push(Lexeme::new(line, col, Token::Label(format!("'{}", name))));
+                continue
             }
 
-            if no_match {
-                return Err(())
+            if c == b'\'' {
+                let len = self.char_literal_len().ok_or(LexErr::MalformedLiteral)?;
+                let lit = String::from_utf8(self.data[self.ptr..self.ptr + len].to_vec()).unwrap();
+
+                lexeme_list.push(Lexeme::new(line, col, Token::Lit(lit)));
+                self.advance(len);
+                continue
             }
 
-            self.advance(1);
+            match self.match_symbol() {
+                Some((tok, len)) => {
+                    lexeme_list.push(Lexeme::new(line, col, tok));
+                    self.advance(len);
+                }
+
+                None => return Err(LexErr::UnknownToken)
+            }
         }
 
         lexeme_list.push(Lexeme::new(self.line, self.col, Token::EOF));
         Ok(lexeme_list)
     }
 
-    fn current(&self) -> u8 {
-        return self.data.as_bytes()[self.ptr]
+    ///the longest symbol starting at the current character
+    fn match_symbol(&self) -> Option<(Token, usize)> {
+        for len in (1..=MAX_SYMBOL_LEN).rev() {
+            let text = match self.data.get(self.ptr..self.ptr + len) {
+                Some(t) => std::str::from_utf8(t).unwrap(),
+                None => continue
+            };
+
+            if let Some(tok) = symbol_token(text) {
+                return Some((tok, len))
+            }
+        }
+
+        None
+    }
+
+    fn is_label_start(&self) -> bool {
+        matches!(self.data.get(self.ptr + 1), Some(x) if *x == b'_' || x.is_ascii_alphabetic())
+    }
+
+    ///length of the character literal starting at the current quote
+    fn char_literal_len(&self) -> Option<usize> {
+        let len = match *self.data.get(self.ptr + 1)? {
+            b'\\' => 2 + escape_value(&self.data[self.ptr + 2..])?.1,
+            b'\'' | b'\n' => return None,
+            _ => 2,
+        };
+
+        match self.data.get(self.ptr + len) {
+            Some(b'\'') => Some(len + 1),
+            _ => None
+        }
+    }
+
+    ///length of the string literal starting at the current quote or b prefix,
+    ///strings end on the same line
+    fn str_literal_len(&self) -> Option<usize> {
+        let mut len = if self.starts_with("b") { 2 } else { 1 };
+
+        loop {
+            match *self.data.get(self.ptr + len)? {
+                b'"' => return Some(len + 1),
+                b'\\' => len += 1 + escape_value(&self.data[self.ptr + len + 1..])?.1,
+                b'\n' => return None,
+                _ => len += 1,
+            }
+        }
+    }
+
+    ///block comments nest, an unterminated one is reported where it starts
+    fn skip_block_comment(&mut self) -> Result<(), LexErr> {
+        let (line, col) = (self.line, self.col);
+        let mut depth: usize = 0;
+
+        loop {
+            if self.starts_with("/*") {
+                depth += 1;
+                self.advance(2);
+            } else if self.starts_with("*/") {
+                depth -= 1;
+                self.advance(2);
+
+                if depth == 0 {
+                    return Ok(())
+                }
+            } else if self.current().is_some() {
+                self.advance(1);
+            } else {
+                self.line = line;
+                self.col = col;
+                return Err(LexErr::UnterminatedComment)
+            }
+        }
+    }
+
+    fn starts_with(&self, prefix: &str) -> bool {
+        self.data[self.ptr..].starts_with(prefix.as_bytes())
+    }
+
+    fn current(&self) -> Option<u8> {
+        self.data.get(self.ptr).copied()
     }
 
     fn advance(&mut self, n: usize) {
         for _ in 0..n {
-            if self.current() == b'\n' {
+            if self.current() == Some(b'\n') {
                 self.line += 1;
                 self.col = 1;
             } else {
@@ -150,9 +234,252 @@ impl Lexer {
         }
     }
 
+    fn take_while(&mut self, pred: fn(u8) -> bool) -> String {
+        let start = self.ptr;
+
+        while self.current().is_some_and(pred) {
+            self.advance(1);
+        }
+
+        String::from_utf8(self.data[start..self.ptr].to_vec()).unwrap()
+    }
+
     fn skip_whitespace(&mut self) {
-        while (self.current() == b' ' || self.current() == b'\n' || self.current() == b'\t' || self.current() == b'\r') && self.current() != 0 {
+        while self.current().is_some_and(|x| x.is_ascii_whitespace()) {
             self.advance(1);
         }
     }
-}
\ No newline at end of file
+}
+
+///value of the escape sequence following a backslash, and how many bytes it takes
+pub fn escape_value(rest: &[u8]) -> Option<(u8, usize)> {
+    let value = match *rest.first()? {
+        b'n' => b'\n',
+        b'r' => b'\r',
+        b't' => b'\t',
+        b'0' => 0,
+        b'\\' => b'\\',
+        b'\'' => b'\'',
+        b'"' => b'"',
+
+        b'x' => {
+            let digits = std::str::from_utf8(rest.get(1..3)?).ok()?;
+            return Some((u8::from_str_radix(digits, 16).ok()?, 3))
+        }
+
+        _ => return None
+    };
+
+    Some((value, 1))
+}
+
+///Bytes of a string literal token, "..." strings get a terminating 0 and b"..." strings do not
+pub fn str_lit_bytes(text: &str) -> Vec<u8> {
+    let (raw, terminated) = match text.strip_prefix('b') {
+        Some(t) => (t, false),
+        None => (text, true),
+    };
+
+    let inner = &raw.as_bytes()[1..raw.len() - 1];
+    let mut bytes: Vec<u8> = vec![];
+    let mut ptr: usize = 0;
+
+    while ptr < inner.len() {
+        if inner[ptr] == b'\\' {
+            let (value, len) = escape_value(&inner[ptr + 1..]).expect("checked by the lexer");
+            bytes.push(value);
+            ptr += 1 + len;
+        } else {
+            bytes.push(inner[ptr]);
+            ptr += 1;
+        }
+    }
+
+    if terminated {
+        bytes.push(0);
+    }
+
+    bytes
+}
+
+fn classify_word(word: String) -> Token {
+    if KEYWORDS.contains(&word.as_str()) {
+        Token::Key(word)
+    } else if WORD_OPS.contains(&word.as_str()) {
+        Token::Op(word)
+    } else {
+        Token::Id(word)
+    }
+}
+
+fn symbol_token(text: &str) -> Option<Token> {
+    let tok = match text {
+        "->" => Token::Arrow,
+
+        "==" | "!=" | "<=" | ">=" | "&&" | "||" | "<" | ">" => Token::Cond(text.to_string()),
+
+        "<<" | ">>" | "~|" | "=" | "+" | "-" | "&" | "*" | "|" | "!" | "/" | "%" | "^" | "~" => Token::Op(text.to_string()),
+
+        "+=" | "-=" | "*=" | "/=" | "%=" | "&=" | "|=" | "^=" | "<<=" | ">>=" | "++" | "--" => Token::Op(text.to_string()),
+
+        ".." | "..=" => Token::Op(text.to_string()),
+
+        "(" => Token::ParenOpen,
+        ")" => Token::ParenClose,
+        "[" => Token::SquareOpen,
+        "]" => Token::SquareClose,
+        "{" => Token::CurlyOpen,
+        "}" => Token::CurlyClose,
+        ";" => Token::SemiCol,
+        ":" => Token::Col,
+        "," => Token::Comma,
+        "." => Token::Period,
+
+        _ => return None
+    };
+
+    Some(tok)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tokens(text: &str) -> Vec<Token> {
+        Lexer::new(text.to_string()).unwrap().lex().unwrap().into_iter().map(|x| x.tok).collect()
+    }
+
+    #[test]
+    fn test_keyword_boundaries() {
+        assert_eq!(tokens("letter iffy format asx as"), vec![
+            Token::Id("letter".to_string()),
+            Token::Id("iffy".to_string()),
+            Token::Id("format".to_string()),
+            Token::Id("asx".to_string()),
+            Token::Op("as".to_string()),
+            Token::EOF,
+        ]);
+
+        assert_eq!(tokens("x as(u8)"), vec![
+            Token::Id("x".to_string()),
+            Token::Op("as".to_string()),
+            Token::ParenOpen,
+            Token::Id("u8".to_string()),
+            Token::ParenClose,
+            Token::EOF,
+        ]);
+    }
+
+    #[test]
+    fn test_longest_match() {
+        assert_eq!(tokens("a<<=b->c!=!d"), vec![
+            Token::Id("a".to_string()),
+            Token::Op("<<=".to_string()),
+            Token::Id("b".to_string()),
+            Token::Arrow,
+            Token::Id("c".to_string()),
+            Token::Cond("!=".to_string()),
+            Token::Op("!".to_string()),
+            Token::Id("d".to_string()),
+            Token::EOF,
+        ]);
+
+        assert_eq!(tokens("i++<<2"), vec![
+            Token::Id("i".to_string()),
+            Token::Op("++".to_string()),
+            Token::Op("<<".to_string()),
+            Token::Lit("2".to_string()),
+            Token::EOF,
+        ]);
+
+        assert_eq!(tokens("'a: 'b' 'c'd"), vec![
+            Token::Label("'a".to_string()),
+            Token::Col,
+            Token::Lit("'b'".to_string()),
+            Token::Lit("'c'".to_string()),
+            Token::Id("d".to_string()),
+            Token::EOF,
+        ]);
+
+        assert_eq!(tokens("0..=9u8..x.y"), vec![
+            Token::Lit("0".to_string()),
+            Token::Op("..=".to_string()),
+            Token::Lit("9u8".to_string()),
+            Token::Op("..".to_string()),
+            Token::Id("x".to_string()),
+            Token::Period,
+            Token::Id("y".to_string()),
+            Token::EOF,
+        ]);
+
+        assert_eq!(tokens("~|~x/y"), vec![
+            Token::Op("~|".to_string()),
+            Token::Op("~".to_string()),
+            Token::Id("x".to_string()),
+            Token::Op("/".to_string()),
+            Token::Id("y".to_string()),
+            Token::EOF,
+        ]);
+
+        let lexemes = Lexer::new("let x\n  = 10;".to_string()).unwrap().lex().unwrap();
+        assert_eq!((lexemes[2].line, lexemes[2].col), (2, 3));
+        assert_eq!((lexemes[4].line, lexemes[4].col), (2, 7));
+
+        assert_eq!(Lexer::new("5abc".to_string()).unwrap().lex(), Err(LexErr::MalformedLiteral));
+        assert!(Lexer::new("x $ y".to_string()).unwrap().lex().is_err());
+    }
+
+    #[test]
+    fn test_comments() {
+        let text = "/// Adds\n///two numbers\nfn // trailing\n/* outer /* nested\n */ still\n comment */ add\n//// not a doc";
+        let lexemes = Lexer::new(text.to_string()).unwrap().lex().unwrap();
+
+        assert_eq!(lexemes.iter().map(|x| x.tok.clone()).collect::<Vec<Token>>(), vec![
+            Token::DocComment("Adds".to_string()),
+            Token::DocComment("two numbers".to_string()),
+            Token::Key("fn".to_string()),
+            Token::Id("add".to_string()),
+            Token::EOF,
+        ]);
+        assert_eq!((lexemes[3].line, lexemes[3].col), (6, 13));
+
+        let mut lexer = Lexer::new("x /* a /* b */".to_string()).unwrap();
+        assert_eq!(lexer.lex(), Err(LexErr::UnterminatedComment));
+        assert_eq!((lexer.line, lexer.col), (1, 3));
+    }
+
+    #[test]
+    fn test_literals() {
+        assert_eq!(tokens("0xFF 0b1010_0001 300u16 'A' '\\'' '\\x41'"), vec![
+            Token::Lit("0xFF".to_string()),
+            Token::Lit("0b1010_0001".to_string()),
+            Token::Lit("300u16".to_string()),
+            Token::Lit("'A'".to_string()),
+            Token::Lit("'\\''".to_string()),
+            Token::Lit("'\\x41'".to_string()),
+            Token::EOF,
+        ]);
+
+        let mut lexer = Lexer::new("x = 0b102;".to_string()).unwrap();
+        assert_eq!(lexer.lex(), Err(LexErr::MalformedLiteral));
+        assert_eq!((lexer.line, lexer.col), (1, 5));
+
+        assert!(Lexer::new("'ab'".to_string()).unwrap().lex().is_err());
+        assert!(Lexer::new("'\\q'".to_string()).unwrap().lex().is_err());
+    }
+
+    #[test]
+    fn test_strings() {
+        assert_eq!(tokens("\"hi\\n\" b\"\\x41\\\"\" bx"), vec![
+            Token::Str("\"hi\\n\"".to_string()),
+            Token::Str("b\"\\x41\\\"\"".to_string()),
+            Token::Id("bx".to_string()),
+            Token::EOF,
+        ]);
+
+        assert_eq!(str_lit_bytes("\"hi\\n\""), vec![b'h', b'i', b'\n', 0]);
+        assert_eq!(str_lit_bytes("b\"\\x41\\\"\""), vec![b'A', b'"']);
+
+        assert_eq!(Lexer::new("\"open\nx\"".to_string()).unwrap().lex(), Err(LexErr::UnterminatedString));
+    }
+}
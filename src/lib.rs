@@ -0,0 +1,392 @@
+mod display;
+pub mod expr;
+pub mod lexer;
+pub mod parser;
+pub mod semantics;
+pub mod stmt;
+pub mod token;
+pub mod typed_ast;
+pub mod types;
+pub mod cgen;
+pub mod emulator;
+
+use cgen::asm_parser::parse_asm;
+use cgen::assembler::assemble;
+use cgen::instruction::{write_asm, AssemblyCommand};
+use cgen::{CodeGenerator, CodegenErr};
+use emulator::Emulator;
+use lexer::Lexer;
+use parser::{ParseError, Parser};
+use semantics::semantic_err::SemanticErr;
+use stmt::Stmt;
+use token::Lexeme;
+use typed_ast::TypedStmt;
+use types::{CustomType, ValueType};
+
+use std::fmt::{Display, Write};
+
+///Last stage the compiler runs, and the artifact it outputs
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Emit {
+    Tokens,
+    Ast,
+    TypedAst,
+    Asm,
+    Bin,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CompileOptions {
+    pub emit: Emit,
+}
+
+///Everything produced by the stages that ran
+#[derive(Debug, Clone, Default)]
+pub struct Artifacts {
+    pub tokens: Vec<Lexeme>,
+    pub ast: Option<Vec<Stmt>>,
+    pub typed_ast: Option<(Vec<TypedStmt>, Vec<CustomType>)>,
+    pub asm: Option<Vec<AssemblyCommand>>,
+    pub bin: Option<Vec<u8>>,
+    pub globals: Vec<(String, ValueType, u16)>, //(NAME, TYPE, ADDRESS)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Stage {
+    Lex,
+    Parse,
+    Semantics,
+    Codegen,
+    Assembly,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub stage: Stage,
+    pub message: String,
+    pub line: Option<usize>,
+    pub col: Option<usize>,
+    pub excerpt: Option<String>, //source line with a caret under the error
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostics {
+    pub items: Vec<Diagnostic>,
+}
+
+impl Emit {
+    pub fn from_name(name: &str) -> Option<Emit> {
+        match name {
+            "tokens" => Some(Emit::Tokens),
+            "ast" => Some(Emit::Ast),
+            "typed-ast" => Some(Emit::TypedAst),
+            "asm" => Some(Emit::Asm),
+            "bin" => Some(Emit::Bin),
+            _ => None
+        }
+    }
+
+    ///text outputs go to stdout when no output path is given
+    pub fn is_text(&self) -> bool {
+        !matches!(self, Emit::Bin)
+    }
+}
+
+impl Diagnostic {
+    pub fn new(stage: Stage, message: String) -> Self {
+        Diagnostic {
+            stage,
+            message,
+            line: None,
+            col: None,
+            excerpt: None,
+        }
+    }
+
+    pub fn with_excerpt(mut self, excerpt: String) -> Self {
+        self.excerpt = Some(excerpt);
+        self
+    }
+
+    pub fn at(mut self, line: usize, col: usize) -> Self {
+        self.line = Some(line);
+        self.col = Some(col);
+        self
+    }
+}
+
+impl From<Diagnostic> for Diagnostics {
+    fn from(d: Diagnostic) -> Self {
+        Diagnostics { items: vec![d] }
+    }
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let stage = match self.stage {
+            Stage::Lex => "Lexing Error",
+            Stage::Parse => "Parsing Error",
+            Stage::Semantics => "Semantic Error",
+            Stage::Codegen => "Code Generation Error",
+            Stage::Assembly => "Assembly Error",
+        };
+
+        write!(f, "{}", stage)?;
+        if let (Some(line), Some(col)) = (self.line, self.col) {
+            write!(f, " at Line: {} Col: {}", line, col)?;
+        }
+
+        write!(f, ": {}", self.message)?;
+
+        if let Some(excerpt) = &self.excerpt {
+            write!(f, "\n{}", excerpt)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Display for Diagnostics {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (n, d) in self.items.iter().enumerate() {
+            if n != 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{}", d)?;
+        }
+
+        Ok(())
+    }
+}
+
+///Runs every stage up to and including `options.emit`
+pub fn compile(source: &str, options: &CompileOptions) -> Result<Artifacts, Diagnostics> {
+    let mut artifacts = Artifacts {
+        tokens: lex(source)?,
+        ..Default::default()
+    };
+
+    if options.emit == Emit::Tokens {
+        return Ok(artifacts)
+    }
+
+    let ast = parse(artifacts.tokens.clone(), source)?;
+    artifacts.ast = Some(ast.clone());
+    if options.emit == Emit::Ast {
+        return Ok(artifacts)
+    }
+
+    let typed_ast = analyze(ast)?;
+    artifacts.typed_ast = Some(typed_ast.clone());
+    if options.emit == Emit::TypedAst {
+        return Ok(artifacts)
+    }
+
+    let (asm, globals) = match generate_code(typed_ast) {
+        Ok(t) => t,
+        Err(e) => return Err(Diagnostic::new(Stage::Codegen, e.to_string()).into())
+    };
+    artifacts.asm = Some(asm.clone());
+    artifacts.globals = globals;
+    if options.emit == Emit::Asm {
+        return Ok(artifacts)
+    }
+
+    artifacts.bin = match assemble(&asm) {
+        Ok(t) => Some(t),
+        Err(e) => return Err(Diagnostic::new(Stage::Assembly, e.to_string()).into())
+    };
+
+    Ok(artifacts)
+}
+
+///Hand-written assembly goes through the same assembler as compiled code,
+///only the `asm` and `bin` artifacts are produced
+pub fn assemble_source(source: &str, options: &CompileOptions) -> Result<Artifacts, Diagnostics> {
+    let asm = match parse_asm(source) {
+        Ok(t) => t,
+        Err(e) => return Err(Diagnostic::new(Stage::Assembly, e.to_string()).at(e.pos().line, e.pos().col).into())
+    };
+
+    let mut artifacts = Artifacts {
+        asm: Some(asm.clone()),
+        ..Default::default()
+    };
+
+    if options.emit != Emit::Bin {
+        return Ok(artifacts)
+    }
+
+    artifacts.bin = match assemble(&asm) {
+        Ok(t) => Some(t),
+        Err(e) => return Err(Diagnostic::new(Stage::Assembly, e.to_string()).into())
+    };
+
+    Ok(artifacts)
+}
+
+pub fn lex(source: &str) -> Result<Vec<Lexeme>, Diagnostic> {
+    let mut lexer = match Lexer::new(source.to_string()) {
+        Ok(t) => t,
+        Err(_) => return Err(Diagnostic::new(Stage::Lex, "Source must be ASCII".to_string()))
+    };
+
+    match lexer.lex() {
+        Ok(t) => Ok(t),
+        Err(e) => Err(Diagnostic::new(Stage::Lex, e.to_string()).at(lexer.line, lexer.col))
+    }
+}
+
+///`source` is only used to quote the offending line
+pub fn parse(lexemes: Vec<Lexeme>, source: &str) -> Result<Vec<Stmt>, Diagnostics> {
+    match Parser::new(lexemes).parse() {
+        Ok(t) => Ok(t),
+        Err(e) => Err(Diagnostics { items: e.iter().map(|x| parse_diagnostic(x, source)).collect() })
+    }
+}
+
+pub fn analyze(ast: Vec<Stmt>) -> Result<(Vec<TypedStmt>, Vec<CustomType>), Diagnostic> {
+    match semantics::generate_typed_ast(ast) {
+        Ok(t) => Ok(t),
+        Err(e) => Err(semantic_diagnostic(e))
+    }
+}
+
+///The program and the top level variables with their addresses
+pub type GeneratedCode = (Vec<AssemblyCommand>, Vec<(String, ValueType, u16)>);
+
+pub fn generate_code(typed_ast: (Vec<TypedStmt>, Vec<CustomType>)) -> Result<GeneratedCode, CodegenErr> {
+    let (stmts, custom_types) = typed_ast;
+
+    let fn_templates = stmts.iter()
+        .filter_map(|x| match x {
+            TypedStmt::FnDeclr(template, _, _) => Some(template.clone()),
+            _ => None
+        })
+        .collect();
+
+    let mut cg = CodeGenerator::new(stmts, custom_types, fn_templates);
+    cg.generate()?;
+
+    Ok((cg.output().clone(), cg.globals().clone()))
+}
+
+fn parse_diagnostic(e: &ParseError, source: &str) -> Diagnostic {
+    Diagnostic::new(Stage::Parse, e.to_string())
+        .at(e.span.line, e.span.col)
+        .with_excerpt(e.excerpt(source))
+}
+
+fn semantic_diagnostic(e: SemanticErr) -> Diagnostic {
+    let d = Diagnostic::new(Stage::Semantics, e.to_string());
+
+    match e.location() {
+        Some(l) => d.at(l.line, l.col),
+        None => d
+    }
+}
+
+impl Artifacts {
+    ///Renders the artifact of `emit`, None if that stage did not run
+    pub fn output(&self, emit: Emit) -> Option<Vec<u8>> {
+        let mut out = String::new();
+
+        match emit {
+            Emit::Tokens => {
+                for l in &self.tokens {
+                    writeln!(out, "{}:{} {}", l.line, l.col, l).unwrap();
+                }
+            }
+
+            Emit::Ast => {
+                for s in self.ast.as_ref()? {
+                    writeln!(out, "{}", s).unwrap();
+                }
+            }
+
+            Emit::TypedAst => {
+                let (typed_ast, custom_types) = self.typed_ast.as_ref()?;
+
+                for t in custom_types {
+                    writeln!(out, "{:#?}", t).unwrap();
+                }
+
+                for stmt in typed_ast {
+                    writeln!(out, "{:#?}", stmt).unwrap();
+                }
+            }
+
+            Emit::Asm => out = write_asm(self.asm.as_ref()?),
+
+            Emit::Bin => return self.bin.clone()
+        }
+
+        Some(out.into_bytes())
+    }
+}
+
+impl Artifacts {
+    ///Value of every top level variable after running the program,
+    ///integers are shown as numbers and everything else as bytes
+    pub fn format_globals(&self, emu: &Emulator) -> String {
+        let custom_types = match &self.typed_ast {
+            Some(t) => &t.1,
+            None => return String::new()
+        };
+
+        let mut out = String::new();
+
+        for (name, v_type, address) in &self.globals {
+            let size = v_type.size(custom_types) as usize;
+            let bytes = &emu.memory[*address as usize..*address as usize + size];
+
+            let mut value: u64 = 0;
+            for (n, b) in bytes.iter().enumerate().take(8) {
+                value |= (*b as u64) << (n * 8);
+            }
+
+            let shown = match v_type {
+                ValueType::U8 | ValueType::U16 | ValueType::U32 | ValueType::U64 |
+                ValueType::Pointer(_) | ValueType::CustomEnum(_) => value.to_string(),
+
+                ValueType::I8 | ValueType::I16 | ValueType::I32 | ValueType::I64 => {
+                    let shift = 64 - size * 8;
+                    (((value << shift) as i64) >> shift).to_string()
+                }
+
+                _ => format!("{:?}", bytes),
+            };
+
+            writeln!(out, "{}: {} = {}", name, v_type, shown).unwrap();
+        }
+
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compile_and_run() {
+        let source = "
+            fn add(a: u16, b: u16) -> u16 {
+                return a + b;
+            }
+
+            let i: u8 = 0;
+            let total: u16 = 0;
+            while i < 5 {
+                total = add(total, i as u16);
+                i = i + 1;
+            }
+            let neg: i16 = -(total as i16);";
+
+        let artifacts = compile(source, &CompileOptions { emit: Emit::Bin }).unwrap();
+        let mut emu = Emulator::new(artifacts.bin.clone().unwrap());
+        emu.run(emulator::DEFAULT_MAX_STEPS).unwrap();
+
+        assert_eq!(artifacts.format_globals(&emu), "i: u8 = 5\ntotal: u16 = 10\nneg: i16 = -10\n");
+    }
+}
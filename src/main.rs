@@ -1,73 +1,110 @@
-mod display;
-mod expr;
-mod lexer;
-mod parser;
-mod semantics;
-mod stmt;
-mod token;
-mod typed_ast;
-mod types;
-mod cgen;
-
-use lexer::*;
-use parser::*;
-use semantics::generate_typed_ast;
+mod cli;
 
+use cli::*;
+use mycc::cgen::disassembler::{disassemble, write_listing};
+use mycc::emulator::{Emulator, DEFAULT_MAX_STEPS};
+use mycc::{assemble_source, compile, CompileOptions, Emit};
+
+use std::path::Path;
 use std::process::exit;
-use std::{fs::File, io::Read};
+use std::fs;
+
+const EXIT_COMPILE_ERR: i32 = 1;
+const EXIT_USAGE_ERR: i32 = 2;
+const EXIT_RUNTIME_ERR: i32 = 3;
 
 fn main() {
-    let mut f = File::open("program.txt").unwrap();
-    let mut text = String::new();
-    f.read_to_string(&mut text).unwrap();
-    text = text.trim().to_string();
-    text.push('\0');
-
-    let mut lexer = Lexer::new(text).unwrap();
-    let lexemes = match lexer.lex() {
-        Ok(t) => t,
-        Err(_) => {
-            println!("Lexing Error at Line: {} Col: {}", lexer.line, lexer.col);
-            exit(0);
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    match Command::from_args(&args) {
+        Ok(Command::Compile(opts)) => compile_file(&opts),
+        Ok(Command::Run(input)) => run_file(&input),
+        Ok(Command::Disasm(input)) => disasm_file(&input),
+
+        Err(e) => {
+            eprintln!("{}\n{}", e, USAGE);
+            exit(EXIT_USAGE_ERR);
+        }
+    }
+}
+
+fn compile_file(opts: &Options) {
+    let artifacts = compile_or_exit(&opts.input, opts.emit);
+    let data = match artifacts.output(opts.emit) {
+        Some(t) => t,
+        None => {
+            eprintln!("{}: Nothing to Emit for this Input", opts.input.display());
+            exit(EXIT_USAGE_ERR);
         }
     };
 
-    for l in &lexemes {
-        println!("{}", l);
+    match &opts.output {
+        Some(path) => write_file(path, &data),
+
+        None => {
+            print!("{}", String::from_utf8_lossy(&data));
+        }
     }
+}
 
-    println!("");
+///compiles and executes the program, then prints the machine state and every top level variable
+fn run_file(input: &Path) {
+    let artifacts = compile_or_exit(input, Emit::Bin);
 
-    let mut parser = Parser::new(lexemes);
-    let ast = match parser.parse() {
+    let mut emu = Emulator::new(artifacts.bin.clone().expect("compiled to a binary"));
+    let result = emu.run(DEFAULT_MAX_STEPS);
+
+    print!("{}", emu);
+    print!("{}", artifacts.format_globals(&emu));
+
+    if let Err(e) = result {
+        eprintln!("{}: Runtime Error: {}", input.display(), e);
+        exit(EXIT_RUNTIME_ERR);
+    }
+}
+
+fn disasm_file(input: &Path) {
+    let image = match fs::read(input) {
         Ok(t) => t,
         Err(e) => {
-            println!("{}", e);
-            exit(0);
+            eprintln!("Cannot Read {}: {}", input.display(), e);
+            exit(EXIT_USAGE_ERR);
         }
     };
 
-    for s in &ast {
-        println!("{}", s);
-    }
+    print!("{}", write_listing(&disassemble(&image)));
+}
 
-    let (typed_ast, custom_types) = match generate_typed_ast(ast) {
+fn compile_or_exit(input: &Path, emit: Emit) -> mycc::Artifacts {
+    let text = match fs::read_to_string(input) {
         Ok(t) => t,
         Err(e) => {
-            println!("{:?}", e);
-            exit(0);
+            eprintln!("Cannot Read {}: {}", input.display(), e);
+            exit(EXIT_USAGE_ERR);
         }
     };
 
-    println!("\n---\n");
+    //hand-written assembly skips straight to the assembler
+    let result = if input.extension().is_some_and(|x| x == "asm") {
+        assemble_source(&text, &CompileOptions { emit })
+    } else {
+        compile(&text, &CompileOptions { emit })
+    };
 
-    for t in custom_types {
-        println!("{:#?}", t);
+    match result {
+        Ok(t) => t,
+        Err(diagnostics) => {
+            for d in diagnostics.items {
+                eprintln!("{}: {}", input.display(), d);
+            }
+            exit(EXIT_COMPILE_ERR);
+        }
     }
+}
 
-    println!("\n---\n");
-
-    for stmt in typed_ast {
-        println!("{:#?}", stmt);
+fn write_file(path: &Path, data: &[u8]) {
+    if let Err(e) = fs::write(path, data) {
+        eprintln!("Cannot Write {}: {}", path.display(), e);
+        exit(EXIT_USAGE_ERR);
     }
 }
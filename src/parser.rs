@@ -4,43 +4,176 @@ use crate::stmt::*;
 
 mod expr_parsing;
 mod stmt_parsing;
+pub mod parse_err;
 
+pub use self::parse_err::{ParseError, Span};
+
+use std::collections::HashMap;
 use std::mem::discriminant;
 
 pub struct Parser {
     data: Vec<Lexeme>,
     ptr: usize,
+    docs: HashMap<usize, String>, //doc comment of the lexeme at an index of `data`
+    errors: Vec<ParseError>, //errors recovered from so far
+    no_struct_literal: bool, //set in the header of if, while and for, where `Name {` starts the body
 }
 
+///keywords that start a statement, recovery resumes at them
+const STMT_KEYWORDS: [&str; 13] = ["let", "static", "const", "fn", "struct", "enum", "loop", "while", "for", "if", "return", "break", "continue"];
+
 impl Parser {
-    pub fn new(data: Vec<Lexeme>) -> Self {
+    ///doc comments are taken out of the token stream
+    ///and attached to the lexeme that follows them
+    pub fn new(lexemes: Vec<Lexeme>) -> Self {
+        let mut data: Vec<Lexeme> = vec![];
+        let mut docs: HashMap<usize, String> = HashMap::new();
+        let mut doc_lines: Vec<String> = vec![];
+
+        for l in lexemes {
+            match l.tok {
+                Token::DocComment(line) => doc_lines.push(line),
+
+                _ => {
+                    if !doc_lines.is_empty() {
+                        docs.insert(data.len(), doc_lines.join("\n"));
+                        doc_lines.clear();
+                    }
+
+                    data.push(l);
+                }
+            }
+        }
+
         Self {
             data,
-            ptr: 0
+            ptr: 0,
+            docs,
+            errors: vec![],
+            no_struct_literal: false,
+        }
+    }
+
+    ///Fails with every error found
+    pub fn parse(&mut self) -> Result<Vec<Stmt>, Vec<ParseError>> {
+        let (ast, errors) = self.parse_all();
+
+        match errors.is_empty() {
+            true => Ok(ast),
+            false => Err(errors),
         }
     }
 
-    pub fn parse(&mut self) -> Result<Vec<Stmt>, &'static str> {
+    ///Statements that failed to parse are left out of the returned AST,
+    ///parsing resumes after them
+    pub fn parse_all(&mut self) -> (Vec<Stmt>, Vec<ParseError>) {
         let mut ast: Vec<Stmt> = vec![];
 
         while self.current().tok != Token::EOF {
-            ast.push(self.parse_stmt()?);
+            if let Some(t) = self.parse_stmt_or_recover() {
+                ast.push(t);
+            }
         }
 
         for stmt in ast.iter_mut() {
-            if let Err(err_position) = stmt.neg_unary_literals() {
-                self.change_ptr_to_lexeme(err_position);
-                return Err("Cannot Fit Literal into i64")
+            if let Err(position) = stmt.neg_unary_literals() {
+                self.errors.push(ParseError::new("Cannot Fit Literal into i64", position, vec![]));
+            }
+        }
+
+        self.errors.sort_by_key(|x| (x.span.line, x.span.col));
+
+        (ast, std::mem::take(&mut self.errors))
+    }
+
+    ///on an error, records it and skips to where the next statement can start
+    fn parse_stmt_or_recover(&mut self) -> Option<Stmt> {
+        let start = self.ptr;
+
+        match self.parse_stmt() {
+            Ok(t) => Some(t),
+
+            Err(e) => {
+                self.errors.push(e);
+                self.synchronize();
+
+                if self.ptr == start && self.current().tok != Token::EOF {
+                    self.ptr += 1;
+                }
+
+                None
+            }
+        }
+    }
+
+    ///skips past the next `;` or `{ ... }`, or up to the next `}` or statement keyword
+    fn synchronize(&mut self) {
+        loop {
+            match &self.current().tok {
+                Token::EOF | Token::CurlyClose => return,
+
+                Token::SemiCol => {
+                    self.ptr += 1;
+                    return
+                }
+
+                //a block ends the statement, like the body of a broken fn declaration
+                Token::CurlyOpen => {
+                    self.skip_block();
+                    return
+                }
+
+                Token::Key(k) if STMT_KEYWORDS.contains(&k.as_str()) => return,
+
+                _ => self.ptr += 1,
             }
         }
+    }
+
+    ///error at the current lexeme
+    fn error(&self, message: &'static str, expected: Vec<Token>) -> ParseError {
+        ParseError::new(message, self.current(), expected)
+    }
+
+    ///consumes `t` or fails with `message`
+    fn expect(&mut self, t: Token, message: &'static str) -> Result<Lexeme, ParseError> {
+        if self.match_tok(t.clone()) {
+            return Ok(self.previous())
+        }
+
+        Err(self.error(message, vec![t]))
+    }
+
+    ///consumes a token of the same kind as `t` or fails with `message`
+    fn expect_type(&mut self, t: Token, message: &'static str) -> Result<Lexeme, ParseError> {
+        if self.match_tok_type(t.clone()) {
+            return Ok(self.previous())
+        }
 
-        Ok(ast)
+        Err(self.error(message, vec![t]))
     }
-    
-    fn change_ptr_to_lexeme(&mut self, target: Lexeme) {
-        self.ptr = 0;
 
-        while self.data[self.ptr] != target {
+    ///skips from a `{` past its matching `}`, or to EOF
+    fn skip_block(&mut self) {
+        let mut depth: usize = 0;
+
+        loop {
+            match self.current().tok {
+                Token::EOF => return,
+                Token::CurlyOpen => depth += 1,
+
+                Token::CurlyClose => {
+                    depth -= 1;
+
+                    if depth == 0 {
+                        self.ptr += 1;
+                        return
+                    }
+                }
+
+                _ => {}
+            }
+
             self.ptr += 1;
         }
     }
@@ -69,15 +202,136 @@ impl Parser {
         self.data[self.ptr].clone()
     }
 
+    ///doc comment right before the previous lexeme
+    fn previous_doc(&self) -> Option<String> {
+        self.docs.get(&(self.ptr - 1)).cloned()
+    }
+
     fn previous(&self) -> Lexeme {
         self.data[self.ptr - 1].clone()
     }
 
+    ///the EOF lexeme when there is nothing after the current one
     fn look_ahead(&self) -> Lexeme {
-        self.data[self.ptr + 1].clone()
+        match self.data.get(self.ptr + 1) {
+            Some(l) => l.clone(),
+            None => self.data.last().expect("lexemes end with EOF").clone()
+        }
     }
 
     fn go_back(&mut self) {
         self.ptr -= 1;
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::Lexer;
+
+    #[test]
+    fn test_doc_comments() {
+        let text = "/// A point\nstruct P { x: u8 }\n/// Does nothing\n/// at all\nfn f() -> u8 { return /// ignored\n 1; }\nlet x: u8 = 1;";
+        let lexemes = Lexer::new(text.to_string()).unwrap().lex().unwrap();
+        let ast = Parser::new(lexemes).parse().unwrap();
+
+        assert!(matches!(&ast[0], Stmt::StructDeclr(_, _, Some(doc)) if doc == "A point"));
+        assert!(matches!(&ast[1], Stmt::FnDeclr(_, _, _, _, Some(doc)) if doc == "Does nothing\nat all"));
+    }
+
+    #[test]
+    fn test_parse_error() {
+        let text = "let x: u8 = 1\nlet y: u8 = 300;";
+        let lexemes = Lexer::new(text.to_string()).unwrap().lex().unwrap();
+        let e = Parser::new(lexemes).parse().unwrap_err().remove(0);
+
+        assert_eq!(e.found.tok, Token::Key("let".to_string()));
+        assert_eq!(e.expected, vec![Token::SemiCol]);
+        assert_eq!(e.span, Span { line: 2, col: 1, len: 3 });
+        assert_eq!(e.excerpt(text), "2 | let y: u8 = 300;\n  | ^^^");
+
+        let text = "let x: u8 = 1;\nlet y: u8 = 300u8;";
+        let lexemes = Lexer::new(text.to_string()).unwrap().lex().unwrap();
+        let e = Parser::new(lexemes).parse().unwrap_err().remove(0);
+
+        assert_eq!(e.message, "Number Literal Out of Range for its Type");
+        assert_eq!(e.excerpt(text), "2 | let y: u8 = 300u8;\n  |             ^^^^^");
+
+        let text = "let x: i8 = -129i8;";
+        let lexemes = Lexer::new(text.to_string()).unwrap().lex().unwrap();
+        let e = Parser::new(lexemes).parse().unwrap_err().remove(0);
+
+        assert_eq!(e.found.tok, Token::Lit("-129i8".to_string()));
+        assert_eq!(e.excerpt(text), "1 | let x: i8 = -129i8;\n  |             ^^^^^^");
+    }
+
+    #[test]
+    fn test_precedence() {
+        let cases = [
+            ("a || b && c", "(a || (b && c))"),
+            ("1 + 2 * 3 << 1", "((1 + (2 * 3)) << 1)"),
+            ("a & b == c", "(a & (b == c))"),
+            ("a | b ^ c & d", "(a | (b ^ (c & d)))"),
+            ("a < b == c > d", "((a < b) == (c > d))"),
+            ("a - b - c", "((a - b) - c)"),
+            ("x << y + 1", "(x << (y + 1))"),
+            ("x as u16 as u32", "((x as Type u16) as Type u32)"),
+            ("-x as u16", "((- x) as Type u16)"),
+            ("!~x * 2", "((! (~ x)) * 2)"),
+            ("*p + 1", "((* p) + 1)"),
+            ("**pp.x", "(* (* pp.x))"),
+            ("(*p).x[1]", "(* p).x[1]"),
+        ];
+
+        for (text, tree) in cases {
+            let lexemes = Lexer::new(format!("{};", text)).unwrap().lex().unwrap();
+            let ast = Parser::new(lexemes).parse().unwrap();
+
+            match &ast[0] {
+                Stmt::ExprStmt(e) => assert_eq!(e.to_string(), tree, "{}", text),
+                _ => panic!("{} is not an expression statement", text),
+            }
+        }
+
+        //assignments display without parentheses
+        let lexemes = Lexer::new("a = b += c;".to_string()).unwrap().lex().unwrap();
+        let ast = Parser::new(lexemes).parse().unwrap();
+
+        assert!(matches!(&ast[0], Stmt::ExprStmt(Expr::Assign(t)) if matches!(t.right, Expr::CompoundAssign(_))));
+    }
+
+    #[test]
+    fn test_struct_literal_in_header() {
+        //`x {` starts the body, a literal in parentheses or arguments is fine
+        let text = "if x { y = 1; }\nwhile f(P { a: 1 }) == b { }\nlet p: P = P { a: 1, };";
+        let lexemes = Lexer::new(text.to_string()).unwrap().lex().unwrap();
+        let ast = Parser::new(lexemes).parse().unwrap();
+
+        assert!(matches!(&ast[0], Stmt::IfStmt(Expr::Primary(_), _, None)));
+        assert!(matches!(&ast[1], Stmt::WhileStmt(None, _, _)));
+        assert!(matches!(&ast[2], Stmt::VarDeclr(_, _, Some(Expr::Primary(p))) if matches!(&**p, PrimaryExpr::StructLiteral(_, fields) if fields.len() == 1)));
+    }
+
+    #[test]
+    fn test_recovery() {
+        let text = "let x: u8 = ;\nfn f(a u8) -> u8 { return a; }\nfn g() -> u8 { let b: u8 = 1 return b; }\nlet y: u8 = 2;\nwhile 1 {";
+        let lexemes = Lexer::new(text.to_string()).unwrap().lex().unwrap();
+        let (ast, errors) = Parser::new(lexemes).parse_all();
+
+        let positions: Vec<(usize, usize)> = errors.iter().map(|x| (x.span.line, x.span.col)).collect();
+        assert_eq!(positions, vec![(1, 13), (2, 8), (3, 30), (5, 10)]);
+
+        //g keeps the statement after the broken one, y is parsed
+        assert_eq!(ast.len(), 2);
+        assert!(matches!(&ast[0], Stmt::FnDeclr(_, _, _, body, _) if matches!(&**body, Stmt::Block(b) if b.len() == 1)));
+        assert!(matches!(&ast[1], Stmt::VarDeclr(name, _, _) if name.data() == "y"));
+
+        //EOF in the middle of an expression
+        for text in ["let x: u8 =", "let x: u8 = 1 +"] {
+            let lexemes = Lexer::new(text.to_string()).unwrap().lex().unwrap();
+            let (_, errors) = Parser::new(lexemes).parse_all();
+
+            assert_eq!(errors.len(), 1);
+            assert_eq!(errors[0].found.tok, Token::EOF);
+        }
+    }
+}
@@ -1,123 +1,173 @@
 use super::*;
+use crate::lexer::str_lit_bytes;
+
+/*
+Binary operators, from the loosest to the tightest binding:
+
+    level   operators                               associativity
+    1       = += -= *= /= %= &= |= ^= <<= >>=       right
+    2       ||                                      left
+    3       &&                                      left
+    4       | ~|                                    left
+    5       ^                                       left
+    6       &                                       left
+    7       == !=                                   left
+    8       < > <= >=                               left
+    9       << >>                                   left
+    10      + -                                     left
+    11      * / %                                   left
+
+Tighter than all of them are casts with `as`, which chain from the left,
+then the prefix operators - ! ~, then postfix ++ -- and primaries.
+*/
+const ASSIGN_LEVEL: u8 = 1;
+
+fn binary_level(tok: &Token) -> Option<u8> {
+    let op = match tok {
+        Token::Op(t) | Token::Cond(t) => t.as_str(),
+        _ => return None
+    };
+
+    let level = match op {
+        "=" | "+=" | "-=" | "*=" | "/=" | "%=" | "&=" | "|=" | "^=" | "<<=" | ">>=" => ASSIGN_LEVEL,
+        "||" => 2,
+        "&&" => 3,
+        "|" | "~|" => 4,
+        "^" => 5,
+        "&" => 6,
+        "==" | "!=" => 7,
+        "<" | ">" | "<=" | ">=" => 8,
+        "<<" | ">>" => 9,
+        "+" | "-" => 10,
+        "*" | "/" | "%" => 11,
+        _ => return None
+    };
+
+    Some(level)
+}
+
+fn make_binary(left: Expr, operator: Lexeme, right: Expr) -> Expr {
+    let binary = Box::new(BinaryExpr { left, operator, right });
+
+    match binary.operator.data().as_str() {
+        "=" => Expr::Assign(binary),
+        "==" | "!=" => Expr::Equality(binary),
+        "<" | ">" | "<=" | ">=" | "&&" | "||" => Expr::Comparison(binary),
+        "+" | "-" | "&" | "|" | "~|" | "^" => Expr::Term(binary),
+        "*" | "/" | "%" => Expr::Factor(binary),
+        "<<" | ">>" => Expr::Shift(binary),
+        _ => Expr::CompoundAssign(binary),
+    }
+}
 
 impl Parser {
-    pub fn parse_expr(&mut self) -> Result<Expr, &'static str> {
-        self.parse_assign()
+    pub fn parse_expr(&mut self) -> Result<Expr, ParseError> {
+        self.parse_binary(ASSIGN_LEVEL)
     }
 
-    fn parse_assign(&mut self) -> Result<Expr, &'static str> {
-        let mut left = self.parse_equality()?;
-
-        if self.match_tok(Token::Op("=".to_string())) {
-            let op = self.previous();
-            let right = self.parse_assign()?;
-
-            left = Expr::Assign(Box::new(BinaryExpr { left , operator: op, right }))
-        }
+    ///an expression before a block, where `Name {` starts the block rather than a struct literal
+    pub fn parse_header_expr(&mut self) -> Result<Expr, ParseError> {
+        let no_struct_literal = std::mem::replace(&mut self.no_struct_literal, true);
+        let e = self.parse_expr();
+        self.no_struct_literal = no_struct_literal;
 
-        Ok(left)
+        e
     }
 
-    fn parse_equality(&mut self) -> Result<Expr, &'static str> {
-        let mut left = self.parse_comparison()?;
+    ///an expression inside brackets, where struct literals are allowed again
+    pub fn parse_inner_expr(&mut self) -> Result<Expr, ParseError> {
+        let no_struct_literal = std::mem::replace(&mut self.no_struct_literal, false);
+        let e = self.parse_expr();
+        self.no_struct_literal = no_struct_literal;
 
-        while self.match_tok(Token::Cond("==".to_string())) || self.match_tok(Token::Cond("!=".to_string())) {
-            let operator = self.previous();
-            let right = self.parse_comparison()?;
-
-            left = Expr::Equality(Box::new(BinaryExpr { left, operator, right }))
-        }
+        e
+    }
 
+    ///precedence climbing over the operators of at least `min_level`
+    fn parse_binary(&mut self, min_level: u8) -> Result<Expr, ParseError> {
+        let mut left = self.parse_cast()?;
 
-        Ok(left)
-    }
+        loop {
+            let level = match binary_level(&self.current().tok) {
+                Some(t) if t >= min_level => t,
+                _ => break
+            };
 
-    fn parse_comparison(&mut self) -> Result<Expr, &'static str> {
-        let mut left = self.parse_term()?;
+            let operator = self.current();
+            self.ptr += 1;
 
-        while self.match_tok(Token::Cond(">".to_string())) || self.match_tok(Token::Cond("<".to_string())) ||
-        self.match_tok(Token::Cond(">=".to_string())) || self.match_tok(Token::Cond("<=".to_string()))  ||
-        self.match_tok(Token::Cond("&&".to_string())) || self.match_tok(Token::Cond("||".to_string())) {
-            let operator = self.previous();
-            let right = self.parse_term()?;
+            //right associative operators take an operand of their own level
+            let right = match level {
+                ASSIGN_LEVEL => self.parse_binary(level)?,
+                _ => self.parse_binary(level + 1)?,
+            };
 
-            left = Expr::Comparison(Box::new(BinaryExpr { left, operator, right }))
+            left = make_binary(left, operator, right);
         }
 
-
         Ok(left)
     }
 
-    fn parse_term(&mut self) -> Result<Expr, &'static str> {
-        let mut left = self.parse_shift()?;
+    fn parse_cast(&mut self) -> Result<Expr, ParseError> {
+        let mut left = self.parse_unary()?;
 
-        while self.match_tok(Token::Op("+".to_string())) || self.match_tok(Token::Op("-".to_string())) ||
-        self.match_tok(Token::Op("&".to_string())) || self.match_tok(Token::Op("|".to_string())) ||
-        self.match_tok(Token::Op("~|".to_string())) {
+        while self.match_tok(Token::Op("as".to_string())) {
             let operator = self.previous();
-            let right = self.parse_shift()?;
+            let to_type = self.parse_type_declr()?;
 
-            left = Expr::Term(Box::new(BinaryExpr { left, operator, right }))
+            left = Expr::Cast(Box::new(left), operator, to_type);
         }
 
-
         Ok(left)
     }
 
-    fn parse_shift(&mut self) -> Result<Expr, &'static str> {
-        let left = self.parse_unary()?;
-
-        if self.match_tok(Token::Op("<<".to_string())) || self.match_tok(Token::Op(">>".to_string())) {
+    fn parse_unary(&mut self) -> Result<Expr, ParseError> {
+        if self.match_tok(Token::Op("!".to_string())) || self.match_tok(Token::Op("-".to_string())) ||
+        self.match_tok(Token::Op("~".to_string())) {
             let operator = self.previous();
 
-            let shift_amount = self.parse_num_literal()?;
-
-            return Ok(Expr::Shift(Box::new(left), operator, shift_amount))
-        }
-
-
-        Ok(left)
-    }
+            //negative literals are read whole, so -128i8 fits its suffix
+            if operator.data() == "-" && matches!(self.current().tok, Token::Lit(_)) {
+                let lexeme = self.current();
+                let num_literal = self.parse_num_literal(Some(&operator))?;
 
-    fn parse_unary(&mut self) -> Result<Expr, &'static str> {
-        if self.match_tok(Token::Op("!".to_string())) || self.match_tok(Token::Op("-".to_string())) {
-            let operator = self.previous();
+                return Ok(Expr::Primary(Box::new(PrimaryExpr::NumLiteral(num_literal, lexeme))))
+            }
 
-            let right = self.parse_cast()?;
+            let right = self.parse_unary()?;
 
             return Ok(Expr::Unary(operator, Box::new(right)))
-
         }
 
-        Ok(self.parse_cast()?)
+        self.parse_increment()
     }
 
-    fn parse_cast(&mut self) -> Result<Expr, &'static str> {
+    ///`x++` and `x--` are read as `x += 1` and `x -= 1`
+    fn parse_increment(&mut self) -> Result<Expr, ParseError> {
         let left = self.parse_fn_call()?;
 
-        if self.match_tok(Token::Op("as".to_string())) {
-            let operator = self.previous();
-            let to_type = self.parse_type_declr()?;
+        if self.match_tok(Token::Op("++".to_string())) || self.match_tok(Token::Op("--".to_string())) {
+            let op = self.previous();
+            let compound_op = if op.data() == "++" { "+=" } else { "-=" };
+
+            let operator = Lexeme::new(op.line, op.col, Token::Op(compound_op.to_string()));
+            let one = Lexeme::new(op.line, op.col, Token::Lit("1".to_string()));
+            let right = Expr::Primary(Box::new(PrimaryExpr::NumLiteral(NumLiteral::U8(1), one)));
 
-            return Ok(Expr::Cast(Box::new(left), operator, to_type))
+            return Ok(Expr::CompoundAssign(Box::new(BinaryExpr { left, operator, right })))
         }
 
         Ok(left)
     }
 
-    fn parse_fn_call(&mut self) -> Result<Expr, &'static str> {
-        if self.look_ahead().tok == Token::ParenOpen {
-            if !self.match_tok_type(ID_TOKEN) {
-                return Err("Expected Identifier for Function Name")
-            }
-
-            let name = self.previous();
+    #[allow(clippy::needless_question_mark)]
+    fn parse_fn_call(&mut self) -> Result<Expr, ParseError> {
+        if matches!(self.current().tok, Token::Id(_)) && self.look_ahead().tok == Token::ParenOpen {
+            let name = self.expect_type(ID_TOKEN, "Expected Identifier for Function Name")?;
             self.match_tok(Token::ParenOpen);
             let args = self.parse_args()?;
 
-            if !self.match_tok(Token::ParenClose) {
-                return Err("Expected Closing Parentheses after Function Call")
-            }
+            self.expect(Token::ParenClose, "Expected Closing Parentheses after Function Call")?;
 
             return Ok(Expr::FnCall(name, args))
         }
@@ -125,49 +175,134 @@ impl Parser {
         Ok(self.parse_primary()?)
     }
 
-    fn parse_primary(&mut self) -> Result<Expr, &'static str> {
+    ///`if c { a } else { b }` where each branch holds a single value,
+    ///the else is required and can be another if expression
+    fn parse_if_expr(&mut self) -> Result<Expr, ParseError> {
+        let key = self.previous();
+        let cond = self.parse_header_expr()?;
+
+        self.expect(Token::CurlyOpen, "Expected Opening Curly Bracket for If Expression Branch")?;
+        let t_value = self.parse_inner_expr()?;
+        self.expect(Token::CurlyClose, "Expected Closing Curly Bracket after If Expression Branch")?;
+
+        self.expect(Token::Key("else".to_string()), "Expected Else Branch for If Expression")?;
+
+        let f_value = match self.match_tok(Token::Key("if".to_string())) {
+            true => self.parse_if_expr()?,
+            false => {
+                self.expect(Token::CurlyOpen, "Expected Opening Curly Bracket for If Expression Branch")?;
+                let f_value = self.parse_inner_expr()?;
+                self.expect(Token::CurlyClose, "Expected Closing Curly Bracket after If Expression Branch")?;
+
+                f_value
+            }
+        };
+
+        Ok(Expr::If(key, Box::new((cond, t_value, f_value))))
+    }
+
+    fn parse_primary(&mut self) -> Result<Expr, ParseError> {
         //NumLiteral
         if matches!(self.current().tok, Token::Lit(_)) {
             let lexeme = self.current();
-            let num_literal = self.parse_num_literal()?;
+            let num_literal = self.parse_num_literal(None)?;
             return Ok(Expr::Primary(Box::new(PrimaryExpr::NumLiteral(num_literal, lexeme))))
 
         }
 
+        //StrLiteral
+        if self.match_tok_type(Token::Str(String::new())) {
+            let lexeme = self.previous();
+            let bytes = str_lit_bytes(&lexeme.data());
+            return Ok(Expr::Primary(Box::new(PrimaryExpr::StrLiteral(bytes, lexeme))))
+        }
+
+        //If
+        if self.match_tok(Token::Key("if".to_string())) {
+            return self.parse_if_expr()
+        }
+
+        //Loop, whose breaks give it a value
+        if matches!(self.current().tok, Token::Label(_)) || self.current().tok == Token::Key("loop".to_string()) {
+            let label = self.parse_label()?;
+            let key = self.expect(Token::Key("loop".to_string()), "Expected Loop after Label")?;
+            let body = self.parse_block()?;
+
+            return Ok(Expr::Loop(key, label, Box::new(body)))
+        }
+
+        //StructLiteral
+        if !self.no_struct_literal && matches!(self.current().tok, Token::Id(_)) && self.look_ahead().tok == Token::CurlyOpen {
+            return self.parse_struct_literal()
+        }
+
+        //ArrayLiteral or ArrayRepeat
+        if self.match_tok(Token::SquareOpen) {
+            let bracket = self.previous();
+            let first = self.parse_inner_expr()?;
+
+            if self.match_tok(Token::SemiCol) {
+                let count = self.parse_inner_expr()?;
+                self.expect(Token::SquareClose, "Expected Closing Square Bracket after Array Literal")?;
+
+                return Ok(Expr::Primary(Box::new(PrimaryExpr::ArrayRepeat(bracket, first, count))))
+            }
+
+            let mut items = vec![first];
+            while self.match_tok(Token::Comma) {
+                if self.current().tok == Token::SquareClose {
+                    break
+                }
+
+                items.push(self.parse_inner_expr()?);
+            }
+
+            self.expect(Token::SquareClose, "Expected Closing Square Bracket after Array Literal")?;
+
+            return Ok(Expr::Primary(Box::new(PrimaryExpr::ArrayLiteral(bracket, items))))
+        }
+
         //Grouping
         if self.match_tok(Token::ParenOpen) {
-            let e = self.parse_expr()?;
+            let e = self.parse_inner_expr()?;
+
+            self.expect(Token::ParenClose, "Expected Closing Parentheses")?;
 
-            if !self.match_tok(Token::ParenClose) {
-                return Err("Expected Closing Parentheses")
+            //a grouped variable can be accessed further, like `(*p).x`
+            if let Expr::Primary(p) = &e {
+                if let PrimaryExpr::Variable(var) = &**p {
+                    if self.current().tok == Token::Period || self.current().tok == Token::SquareOpen {
+                        let var = self.parse_var_access(var.clone())?;
+                        return Ok(Expr::Primary(Box::new(PrimaryExpr::Variable(var))))
+                    }
+                }
             }
 
             return Ok(Expr::Primary(Box::new(PrimaryExpr::Grouping(e))))
         }
 
         //Ref
-        if self.match_tok(Token::Op("&".to_string())) || self.match_tok(Token::Op("*".to_string())) {
+        if self.match_tok(Token::Op("&".to_string())) {
             let operator = self.previous();
             let var = self.parse_var()?;
 
             return Ok(Expr::Primary(Box::new(PrimaryExpr::Ref(operator, var))))
         }
 
-        if self.look_ahead().tok == Token::Col {
-            if !self.match_tok_type(ID_TOKEN) {
-                return Err("Expected Identifier for Enum Name")
-            }
+        //Deref, of any pointer
+        if self.match_tok(Token::Op("*".to_string())) {
+            let operator = self.previous();
+            let pointer = self.parse_unary()?;
 
-            let name = self.previous();
+            return Ok(Expr::Primary(Box::new(PrimaryExpr::Variable(Variable::Deref(operator, Box::new(pointer))))))
+        }
+
+        if self.look_ahead().tok == Token::Col {
+            let name = self.expect_type(ID_TOKEN, "Expected Identifier for Enum Name")?;
             self.match_tok(Token::Col);
-            if !self.match_tok(Token::Col) {
-                return Err("Expected Double Colon :: after Enum Name")
-            }
+            self.expect(Token::Col, "Expected Double Colon :: after Enum Name")?;
 
-            if !self.match_tok_type(ID_TOKEN) {
-                return Err("Expected Identifier for Enum Variant")
-            }
-            let variant = self.previous();
+            let variant = self.expect_type(ID_TOKEN, "Expected Identifier for Enum Variant")?;
 
             return Ok(Expr::Primary(Box::new(PrimaryExpr::EnumVariant(name, variant))))
 
@@ -179,32 +314,52 @@ impl Parser {
         Ok(Expr::Primary(Box::new(PrimaryExpr::Variable(var))))
     }
 
-    fn parse_num_literal(&mut self) -> Result<NumLiteral, &'static str> {
-        if self.match_tok_type(LIT_TOKEN) {
-            let lit = self.previous();
-            
-            let num = match lit.data().parse::<u64>() {
-                Ok(t) => t,
-                Err(_) => return Err("Number Literal Error")
-            };
+    ///`Name { field: value, ... }`, a trailing comma is allowed
+    fn parse_struct_literal(&mut self) -> Result<Expr, ParseError> {
+        let name = self.expect_type(ID_TOKEN, "Expected Identifier for Struct Name")?;
+        self.expect(Token::CurlyOpen, "Expected Opening Curly Bracket for Struct Literal")?;
 
-            if num <= u32::MAX.into() {
-                if num <= u16::MAX.into() {
-                    if num <= u8::MAX.into() {
-                        return Ok(NumLiteral::U8(num as u8))
-                    }
-                    return Ok(NumLiteral::U16(num as u16))
-                }
-                return Ok(NumLiteral::U32(num as u32))
+        let mut fields: Vec<(Lexeme, Expr)> = vec![];
+
+        while self.current().tok != Token::CurlyClose {
+            let field = self.expect_type(ID_TOKEN, "Expected Identifier for Struct Field")?;
+            self.expect(Token::Col, "Expected Colon after Struct Field")?;
+
+            fields.push((field, self.parse_inner_expr()?));
+
+            if !self.match_tok(Token::Comma) {
+                break
             }
+        }
 
-            return Ok(NumLiteral::U64(num))
+        self.expect(Token::CurlyClose, "Expected Closing Curly Bracket after Struct Literal")?;
+
+        Ok(Expr::Primary(Box::new(PrimaryExpr::StructLiteral(name, fields))))
+    }
+
+    ///`minus` is the sign right before the literal, an error then covers both
+    fn parse_num_literal(&mut self, minus: Option<&Lexeme>) -> Result<NumLiteral, ParseError> {
+        let lit = self.expect_type(LIT_TOKEN, "Expected Number Literal")?;
+
+        if let Ok(t) = NumLiteral::from_lit(&lit.data(), minus.is_some()) {
+            return Ok(t)
         }
 
-        Err("Expected Number Literal")
+        match minus {
+            Some(minus) => {
+                let found = Lexeme::new(minus.line, minus.col, Token::Lit(format!("-{}", lit.data())));
+                let mut e = ParseError::new("Number Literal Out of Range for its Type", found, vec![]);
+                e.span = Span::through(minus, &lit);
+
+                Err(e)
+            }
+
+            None => Err(ParseError::new("Number Literal Out of Range for its Type", lit, vec![]))
+        }
     }
 
-    fn parse_args(&mut self) -> Result<Args, &'static str> {
+    #[allow(clippy::needless_return)]
+    fn parse_args(&mut self) -> Result<Args, ParseError> {
         let mut args: Vec<Expr> = vec![];
 
         if self.match_tok(Token::ParenClose) || self.match_tok(Token::SquareClose) || self.match_tok(Token::CurlyClose) {
@@ -212,43 +367,42 @@ impl Parser {
             return Ok(Args { items: args })
         }
         
-        let e = self.parse_expr()?;
+        let e = self.parse_inner_expr()?;
         args.push(e);
 
         while self.match_tok(Token::Comma) {
-            let e = self.parse_expr()?;
+            let e = self.parse_inner_expr()?;
             args.push(e);
         }
 
         return Ok(Args { items: args })
     }
 
-    fn parse_var(&mut self) -> Result<Variable, &'static str> {
-        if !self.match_tok_type(ID_TOKEN) {
-            return Err("Variable Access Parsing Error")    
-        }
+    fn parse_var(&mut self) -> Result<Variable, ParseError> {
+        let id = self.expect_type(ID_TOKEN, "Variable Access Parsing Error")?;
+        self.parse_var_access(Variable::Id(id))
+    }
 
-        let id = self.previous();
-        let mut result = Variable::Id(id);
+    ///field accesses and indexes after `head`, the leftmost one is innermost
+    fn parse_var_access(&mut self, head: Variable) -> Result<Variable, ParseError> {
+        let mut result = head;
 
         while self.current().tok == Token::Period || self.current().tok == Token::SquareOpen {
             if self.match_tok(Token::Period) {
-                let field = self.parse_var()?;
+                let field = self.expect_type(ID_TOKEN, "Expected Identifier for Struct Field")?;
 
-                result = Variable::StructField(Box::new((result, field)));
+                result = Variable::StructField(Box::new((result, Variable::Id(field))));
             }
 
             if self.match_tok(Token::SquareOpen) {
-                let index = self.parse_expr()?;
+                let index = self.parse_inner_expr()?;
                 
-                if !self.match_tok(Token::SquareClose) {
-                    return Err("Expected Closing Square Bracket for Array Index")
-                }
+                self.expect(Token::SquareClose, "Expected Closing Square Bracket for Array Index")?;
 
                 result = Variable::Array(Box::new(result), index);
             }
         }
 
-        return Ok(result)
+        Ok(result)
     }
 }
\ No newline at end of file
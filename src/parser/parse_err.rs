@@ -0,0 +1,64 @@
+use crate::token::*;
+
+///Where a lexeme sits in the source, `len` in characters
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Span {
+    pub line: usize,
+    pub col: usize,
+    pub len: usize,
+}
+
+///The lexeme the parser could not use, with the tokens that would have been accepted there
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+    pub message: &'static str,
+    pub found: Lexeme,
+    pub expected: Vec<Token>,
+    pub span: Span,
+}
+
+impl Span {
+    pub fn of(lexeme: &Lexeme) -> Self {
+        let len = match lexeme.tok {
+            Token::EOF => 1,
+            _ => lexeme.tok.to_string().len(),
+        };
+
+        Span { line: lexeme.line, col: lexeme.col, len }
+    }
+
+    ///From the start of `first` through the end of `last`, just `first` if they are on different lines
+    pub fn through(first: &Lexeme, last: &Lexeme) -> Self {
+        let last_span = Span::of(last);
+
+        match first.line == last.line {
+            true => Span { line: first.line, col: first.col, len: last_span.col + last_span.len - first.col },
+            false => Span::of(first),
+        }
+    }
+}
+
+impl ParseError {
+    pub fn new(message: &'static str, found: Lexeme, expected: Vec<Token>) -> Self {
+        ParseError {
+            message,
+            span: Span::of(&found),
+            found,
+            expected,
+        }
+    }
+
+    ///The line of `source` holding the error with a caret under the span
+    pub fn excerpt(&self, source: &str) -> String {
+        let line = source.lines().nth(self.span.line - 1).unwrap_or("");
+        let number = self.span.line.to_string();
+
+        //the caret stays within the line, EOF points right after its end
+        let start = (self.span.col - 1).min(line.len());
+        let len = self.span.len.min(line.len().saturating_sub(start)).max(1);
+
+        format!("{} | {}\n{} | {}{}",
+            number, line,
+            " ".repeat(number.len()), " ".repeat(start), "^".repeat(len))
+    }
+}
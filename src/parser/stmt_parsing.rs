@@ -1,7 +1,8 @@
 use super::*;
 
 impl Parser {
-    pub fn parse_type_declr(&mut self) -> Result<TypeDeclr, &'static str> {
+    #[allow(clippy::needless_return)]
+    pub fn parse_type_declr(&mut self) -> Result<TypeDeclr, ParseError> {
         if self.match_tok(Token::Arrow) {
             let points_to = self.parse_type_declr()?;
             return Ok(TypeDeclr::Pointer(Box::new(points_to)))
@@ -10,46 +11,29 @@ impl Parser {
         if self.match_tok(Token::SquareOpen) {
             let array_of_type = self.parse_type_declr()?;
             
-            if !self.match_tok(Token::SemiCol) {
-                return Err("Expected Semicolon after Array Item Type")
-            }
-
-            if !self.match_tok_type(LIT_TOKEN) {
-                return Err("Expected Number Literal for Array Size")
-            }
+            self.expect(Token::SemiCol, "Expected Semicolon after Array Item Type")?;
 
-            let array_size = self.previous();
+            let array_size = self.parse_inner_expr()?;
 
-            let array_size = match array_size.data().parse::<u16>() {
-                Ok(t) => t,
-                Err(_) => return Err("Array Size Error")
-            };
+            self.expect(Token::SquareClose, "Expected Closing Square Bracket for Array Type Declaration")?;
 
-            if !self.match_tok(Token::SquareClose) {
-                return Err("Expected Closing Square Bracket for Array Type Declaration")
-            }
-
-            return Ok(TypeDeclr::Array(Box::new(array_of_type), array_size))
+            return Ok(TypeDeclr::Array(Box::new(array_of_type), Box::new(array_size)))
         }
 
         if self.match_tok_type(ID_TOKEN) {
             return Ok(TypeDeclr::Basic(self.previous()))
         }
 
-        return Err("Expected Identifier for Type Declaration")
+        return Err(self.error("Expected Identifier for Type Declaration", vec![Token::Arrow, Token::SquareOpen, ID_TOKEN]))
     }
 
-    pub fn parse_stmt(&mut self) -> Result<Stmt, &'static str> {
+    #[allow(clippy::needless_return)]
+    pub fn parse_stmt(&mut self) -> Result<Stmt, ParseError> {
         //VarDeclr
         if self.match_tok(Token::Key("let".to_string())) {
-            if !self.match_tok_type(ID_TOKEN) {
-                return Err("Expected Identifier for Variable Name")
-            }
-            let name = self.previous();
+            let name = self.expect_type(ID_TOKEN, "Expected Identifier for Variable Name")?;
 
-            if !self.match_tok(Token::Col) {
-                return Err("Expected Colon after Variable Name")
-            }
+            self.expect(Token::Col, "Expected Colon after Variable Name")?;
 
             let var_type = self.parse_type_declr()?;
             
@@ -59,71 +43,72 @@ impl Parser {
                 value = Some(e);
             }
 
-            if !self.match_tok(Token::SemiCol) {
-                return Err("Expected Semicolon after Variable Declaration")
-            }
+            self.expect(Token::SemiCol, "Expected Semicolon after Variable Declaration")?;
 
             return Ok(Stmt::VarDeclr(name, var_type, value))
         }
 
+        //StaticDeclr and ConstDeclr, which always have a value
+        if self.match_tok(Token::Key("static".to_string())) || self.match_tok(Token::Key("const".to_string())) {
+            let key = self.previous();
+            let name = self.expect_type(ID_TOKEN, "Expected Identifier for Variable Name")?;
+
+            self.expect(Token::Col, "Expected Colon after Variable Name")?;
+
+            let var_type = self.parse_type_declr()?;
+
+            self.expect(Token::Op("=".to_string()), "Expected Initial Value for Static or Const")?;
+            let value = self.parse_expr()?;
+
+            self.expect(Token::SemiCol, "Expected Semicolon after Variable Declaration")?;
+
+            return match key.data().as_str() {
+                "static" => Ok(Stmt::StaticDeclr(name, var_type, value)),
+                _ => Ok(Stmt::ConstDeclr(name, var_type, value)),
+            }
+        }
+
         //FnDeclr
         if self.match_tok(Token::Key("fn".to_string())) {
-            if !self.match_tok_type(ID_TOKEN) {
-                return Err("Expected Identifier for Function Name")
-            }
-            let name = self.previous();
+            let doc = self.previous_doc();
 
-            if !self.match_tok(Token::ParenOpen) {
-                return Err("Expected Opening Parentheses after Function Name")
-            }
+            let name = self.expect_type(ID_TOKEN, "Expected Identifier for Function Name")?;
+
+            self.expect(Token::ParenOpen, "Expected Opening Parentheses after Function Name")?;
 
             let params = self.parse_parameters()?;
 
-            if !self.match_tok(Token::ParenClose) {
-                return Err("Expected Closing Parentheses after Function Parameters")
-            }
+            self.expect(Token::ParenClose, "Expected Closing Parentheses after Function Parameters")?;
 
-            if !self.match_tok(Token::Arrow) {
-                return Err("Expected Arrow to Denote Return Type")
-            }
+            self.expect(Token::Arrow, "Expected Arrow to Denote Return Type")?;
 
             let ret_type = self.parse_type_declr()?;
 
             let body = self.parse_block()?;
 
-            return Ok(Stmt::FnDeclr(name, params, ret_type, Box::new(body)))
+            return Ok(Stmt::FnDeclr(name, params, ret_type, Box::new(body), doc))
         }
 
         //StructDeclr
         if self.match_tok(Token::Key("struct".to_string())) {
-            if !self.match_tok_type(ID_TOKEN) {
-                return Err("Expected Identifier for Struct Name")
-            }
-            let name = self.previous();
+            let doc = self.previous_doc();
 
-            if !self.match_tok(Token::CurlyOpen) {
-                return Err("Expected Opening Curly Bracket for Struct Definition")
-            }
+            let name = self.expect_type(ID_TOKEN, "Expected Identifier for Struct Name")?;
+
+            self.expect(Token::CurlyOpen, "Expected Opening Curly Bracket for Struct Definition")?;
 
             let fields = self.parse_parameters()?;
 
-            if !self.match_tok(Token::CurlyClose) {
-                return Err("Expected Closing Curly Bracket after Struct Definition")
-            }
+            self.expect(Token::CurlyClose, "Expected Closing Curly Bracket after Struct Definition")?;
 
-            return Ok(Stmt::StructDeclr(name, fields))
+            return Ok(Stmt::StructDeclr(name, fields, doc))
         }
 
         //EnumDeclr
         if self.match_tok(Token::Key("enum".to_string())) {
-            if !self.match_tok_type(ID_TOKEN) {
-                return Err("Expected Identifier for Enum Name")
-            }
-            let name = self.previous();
+            let name = self.expect_type(ID_TOKEN, "Expected Identifier for Enum Name")?;
 
-            if !self.match_tok(Token::CurlyOpen) {
-                return Err("Expected Opening Curly Bracket after Enum Name")
-            }
+            self.expect(Token::CurlyOpen, "Expected Opening Curly Bracket after Enum Name")?;
 
             let mut variants: Vec<Lexeme> = vec![];
 
@@ -131,45 +116,75 @@ impl Parser {
                 variants.push(self.previous());
 
                 while self.match_tok(Token::Comma) {
-                    if !self.match_tok_type(ID_TOKEN) {
-                        return Err("Expected Identifier for Enum Variant Name")
-                    }
-
-                    variants.push(self.previous());
+                    variants.push(self.expect_type(ID_TOKEN, "Expected Identifier for Enum Variant Name")?);
                 }
 
             }
 
-            if !self.match_tok(Token::CurlyClose) {
-                return Err("Expected Closing Curly Bracket after Enum Definition")
-            }
+            self.expect(Token::CurlyClose, "Expected Closing Curly Bracket after Enum Definition")?;
 
             return Ok(Stmt::EnumDeclr(name, variants))
         }
 
+        //loops can be labeled
+        let label = self.parse_label()?;
+
         //LoopStmt
         if self.match_tok(Token::Key("loop".to_string())) {
             let body = self.parse_block()?;
 
-            return Ok(Stmt::LoopStmt(Box::new(body)))
+            return Ok(Stmt::LoopStmt(label, Box::new(body)))
         }
 
         //WhileStmt
         if self.match_tok(Token::Key("while".to_string())) {
-            let cond = self.parse_expr()?;
+            let cond = self.parse_header_expr()?;
+            let body = self.parse_block()?;
+
+            return Ok(Stmt::WhileStmt(label, cond, Box::new(body)))
+        }
+
+        //ForStmt, over a range or the items of an array
+        if self.match_tok(Token::Key("for".to_string())) {
+            let var = self.expect_type(ID_TOKEN, "Expected Identifier for Loop Variable")?;
+
+            let mut var_type = None;
+            if self.match_tok(Token::Col) {
+                var_type = Some(self.parse_type_declr()?);
+            }
+
+            self.expect(Token::Key("in".to_string()), "Expected In after Loop Variable")?;
+
+            let iterated = self.parse_header_expr()?;
+
+            let mut range_end = None;
+            if self.match_tok(Token::Op("..".to_string())) || self.match_tok(Token::Op("..=".to_string())) {
+                let op = self.previous();
+                range_end = Some((op, self.parse_header_expr()?));
+            }
+
             let body = self.parse_block()?;
 
-            return Ok(Stmt::WhileStmt(cond, Box::new(body)))
+            return Ok(Stmt::ForStmt(label, var, var_type, Box::new((iterated, range_end)), Box::new(body)))
+        }
+
+        if label.is_some() {
+            return Err(self.error("Expected Loop after Label", vec![
+                Token::Key("loop".to_string()), Token::Key("while".to_string()), Token::Key("for".to_string())]))
         }
 
         //IfStmt
         if self.match_tok(Token::Key("if".to_string())) {
-            let cond = self.parse_expr()?;
+            let cond = self.parse_header_expr()?;
             let t_branch = self.parse_block()?;
             let mut f_branch = None;
             
+            //else if chains nest as the false branch
             if self.match_tok(Token::Key("else".to_string())) {
-                f_branch = Some(Box::new(self.parse_block()?));
+                f_branch = match self.current().tok == Token::Key("if".to_string()) {
+                    true => Some(Box::new(self.parse_stmt()?)),
+                    false => Some(Box::new(self.parse_block()?)),
+                };
             }
 
             return Ok(Stmt::IfStmt(cond, Box::new(t_branch), f_branch))
@@ -181,60 +196,107 @@ impl Parser {
             let key = self.previous();
 
             let value = self.parse_expr()?;
+
+            self.expect(Token::SemiCol, "Expected Semicolon after Return Statement")?;
+
             return Ok(Stmt::ReturnStmt(key, value))
         }
 
-        //BreakStmt
+        //BreakStmt, with an optional label and value
         if self.match_tok(Token::Key("break".to_string())) {
-            return Ok(Stmt::BreakStmt(self.previous()))
+            let key = self.previous();
+
+            let mut label = None;
+            if self.match_tok_type(Token::Label(String::new())) {
+                label = Some(self.previous());
+            }
+
+            let mut value = None;
+            if self.current().tok != Token::SemiCol {
+                value = Some(self.parse_expr()?);
+            }
+
+            self.expect(Token::SemiCol, "Expected Semicolon after Break Statement")?;
+
+            return Ok(Stmt::BreakStmt(key, label, value))
+        }
+
+        //ContinueStmt
+        if self.match_tok(Token::Key("continue".to_string())) {
+            let key = self.previous();
+
+            let mut label = None;
+            if self.match_tok_type(Token::Label(String::new())) {
+                label = Some(self.previous());
+            }
+
+            self.expect(Token::SemiCol, "Expected Semicolon after Continue Statement")?;
+
+            return Ok(Stmt::ContinueStmt(key, label))
         }
 
         //ExprStmt
         let e = self.parse_expr()?;
-        if !self.match_tok(Token::SemiCol) {
-            return Err("Expected Semicolon after Expression Statement")
-        }
+        self.expect(Token::SemiCol, "Expected Semicolon after Expression Statement")?;
 
         return Ok(Stmt::ExprStmt(e))
     }
 
-    fn parse_block(&mut self) -> Result<Stmt, &'static str> {
-        if !self.match_tok(Token::CurlyOpen) {
-            return Err("Expected Opening Curly Bracket for Block")
+    ///`'name:` before a loop
+    pub fn parse_label(&mut self) -> Result<Option<Lexeme>, ParseError> {
+        if !self.match_tok_type(Token::Label(String::new())) {
+            return Ok(None)
         }
 
+        let label = self.previous();
+        self.expect(Token::Col, "Expected Colon after Loop Label")?;
+
+        Ok(Some(label))
+    }
+
+    pub fn parse_block(&mut self) -> Result<Stmt, ParseError> {
+        self.expect(Token::CurlyOpen, "Expected Opening Curly Bracket for Block")?;
+
+        //a loop expression's body can be in a header
+        let no_struct_literal = std::mem::replace(&mut self.no_struct_literal, false);
+        let block = self.parse_block_body();
+        self.no_struct_literal = no_struct_literal;
+
+        block
+    }
+
+    fn parse_block_body(&mut self) -> Result<Stmt, ParseError> {
         let mut body: Vec<Stmt> = vec![];
 
         while !self.match_tok(Token::CurlyClose) {
-            body.push(self.parse_stmt()?);
+            if self.current().tok == Token::EOF {
+                return Err(self.error("Expected Closing Curly Bracket after Block", vec![Token::CurlyClose]))
+            }
+
+            if let Some(t) = self.parse_stmt_or_recover() {
+                body.push(t);
+            }
         }
 
-        return Ok(Stmt::Block(body))
+        Ok(Stmt::Block(body))
     }
 
-    fn parse_parameters(&mut self) -> Result<Parameters, &'static str> {
+    #[allow(clippy::needless_return)]
+    fn parse_parameters(&mut self) -> Result<Parameters, ParseError> {
         let mut params: Vec<(Lexeme, TypeDeclr)> = vec![];
 
         if self.match_tok_type(ID_TOKEN) {
             let name = self.previous();
 
-            if !self.match_tok(Token::Col) {
-                return Err("Expected Colon after Parameter Name")
-            }
+            self.expect(Token::Col, "Expected Colon after Parameter Name")?;
 
             let t = self.parse_type_declr()?;
             params.push((name, t));
 
             while self.match_tok(Token::Comma) {
-                if !self.match_tok_type(ID_TOKEN) {
-                    return Err("Expected Identifier for Parameter Name")
-                }
+                let name = self.expect_type(ID_TOKEN, "Expected Identifier for Parameter Name")?;
 
-                let name = self.previous();
-
-                if !self.match_tok(Token::Col) {
-                    return Err("Expected Colon after Parameter Name")
-                }
+                self.expect(Token::Col, "Expected Colon after Parameter Name")?;
 
                 let t = self.parse_type_declr()?;
                 params.push((name, t));
@@ -7,24 +7,37 @@ use crate::typed_ast::*;
 pub mod semantic_err;
 mod stmt_semantics;
 mod expr_semantics;
+mod const_eval;
 
 use semantic_err::*;
 
+#[derive(Clone)]
 pub struct ScopeStack {
     stack: Vec<ScopeStackOp>,
     used_ids: Vec<String>,
     global_used_ids: Vec<String>,
     pub defined_types: Vec<CustomType>,
     pub defined_functions: Vec<FnTemplate>,
+    defined_consts: Vec<(String, TypedExpr)>, //(NAME, FOLDED VALUE)
 }
 
+#[derive(Clone)]
 enum ScopeStackOp {
     EnterScope(Vec<String>),
-    EnterBreakable,
+    EnterBreakable(Option<String>, BreakValue), //(LABEL, WHAT ITS BREAKS CARRY)
     EnterReturnable(ValueType),
     DeclrVar(String, ValueType),
 }
 
+///What the breaks out of a loop carry
+#[derive(Clone)]
+enum BreakValue {
+    Nothing, //loop statements
+    Unknown, //a loop expression before its first break
+    Value(ValueType),
+}
+
+#[allow(clippy::result_large_err, clippy::unnecessary_cast)]
 pub fn generate_typed_ast(ast: Vec<Stmt>) -> Result<(Vec<TypedStmt>, Vec<CustomType>), SemanticErr> {
     let mut ss = ScopeStack::new();
     let mut typed_ast: Vec<TypedStmt> = vec![];
@@ -62,7 +75,7 @@ pub fn generate_typed_ast(ast: Vec<Stmt>) -> Result<(Vec<TypedStmt>, Vec<CustomT
 
     //second pass, adding every custom struct's name to being_defined
     for stmt in ast.iter() {
-        if let Stmt::StructDeclr(struct_name, _) = stmt {
+        if let Stmt::StructDeclr(struct_name, _, _) = stmt {
             if ss.global_used_ids.contains(&struct_name.data()) || being_defined.contains(&struct_name.data()) {
                 return Err(SemanticErr::UsedId(struct_name.clone()))
             }
@@ -74,7 +87,7 @@ pub fn generate_typed_ast(ast: Vec<Stmt>) -> Result<(Vec<TypedStmt>, Vec<CustomT
     let mut struct_templates: Vec<StructTemplate> = vec![];
     //third pass, generating all struct templates
     for stmt in ast.iter() {
-        if let Stmt::StructDeclr(struct_name, params) = stmt {
+        if let Stmt::StructDeclr(struct_name, params, _) = stmt {
             let typed_params = params.generate_typed_params(&ss, Some(&being_defined))?;
             
             let template = StructTemplate {
@@ -117,9 +130,21 @@ pub fn generate_typed_ast(ast: Vec<Stmt>) -> Result<(Vec<TypedStmt>, Vec<CustomT
         ss.declare_custom_type(CustomType::CustomStruct(template));
     }    
 
+    //statics and consts are declared before any code, so every function sees them
+    let mut typed_globals: Vec<TypedStmt> = vec![];
+    for stmt in ast.iter() {
+        if let Stmt::StaticDeclr(..) | Stmt::ConstDeclr(..) = stmt {
+            typed_globals.push(stmt.generate_typed_stmt(&mut ss, false)?);
+        }
+    }
+
     //generating AST
+    let mut typed_globals = typed_globals.into_iter();
     for stmt in ast.iter() {
-        typed_ast.push(stmt.generate_typed_stmt(&mut ss, false)?);
+        match stmt {
+            Stmt::StaticDeclr(..) | Stmt::ConstDeclr(..) => typed_ast.push(typed_globals.next().unwrap()),
+            _ => typed_ast.push(stmt.generate_typed_stmt(&mut ss, false)?),
+        }
     }
     
     Ok((typed_ast, ss.defined_types))
@@ -127,6 +152,7 @@ pub fn generate_typed_ast(ast: Vec<Stmt>) -> Result<(Vec<TypedStmt>, Vec<CustomT
 
 //ScopeStack helper functions
 impl ScopeStack {
+    #[allow(clippy::new_without_default)]
     pub fn new() -> Self {
         ScopeStack {
             stack: vec![],
@@ -134,6 +160,7 @@ impl ScopeStack {
             global_used_ids: vec![],
             defined_types: vec![],
             defined_functions: vec![],
+            defined_consts: vec![],
         }
     }
     
@@ -150,14 +177,15 @@ impl ScopeStack {
             ptr -= 1;
         }
 
-        if let ScopeStackOp::EnterScope(ids) = &self.stack[ptr] {
-            self.used_ids = ids.clone();
+        if let Some(ScopeStackOp::EnterScope(ids)) = self.stack.pop() {
+            self.used_ids = ids;
             return
         }
 
         unreachable!()
     }
 
+    #[allow(clippy::needless_return)]
     pub fn get_var_type_from_name(&self, target_name: String) -> Option<ValueType> {
         if target_name == "void" {
             return Some(ValueType::Void)
@@ -173,6 +201,11 @@ impl ScopeStack {
         return None
     }
 
+    pub fn get_const_from_name(&self, target_name: String) -> Option<TypedExpr> {
+        self.defined_consts.iter().find(|x| x.0 == target_name).map(|x| x.1.clone())
+    }
+
+    #[allow(clippy::needless_return)]
     pub fn get_fn_from_name(&self, target_name: String) -> Option<FnTemplate> {
         for func in self.defined_functions.iter() {
             if func.name == target_name {
@@ -183,6 +216,7 @@ impl ScopeStack {
         return None
     }
 
+    #[allow(clippy::needless_return)]
     pub fn get_nearest_ret_type(&self) -> Option<ValueType> {
         for stack_op in self.stack.iter().rev() {
             if let ScopeStackOp::EnterReturnable(ret_type) = stack_op {
@@ -193,16 +227,103 @@ impl ScopeStack {
         return None
     }
 
-    pub fn check_if_breakable(&self) -> bool {
+    ///Index in the stack of the loop a break or continue leaves,
+    ///and how many loops are left before it
+    #[allow(clippy::result_large_err)]
+    fn find_breakable(&self, key: &Lexeme, label: &Option<Lexeme>) -> Result<(usize, usize), SemanticErr> {
+        let is_target = |name: &Option<String>| match label {
+            Some(l) => name.as_deref() == Some(l.data().as_str()),
+            None => true,
+        };
+
+        let labeled = self.stack.iter().any(|x| matches!(x, ScopeStackOp::EnterBreakable(name, _) if is_target(name)));
+
+        match label {
+            Some(l) if !labeled => return Err(SemanticErr::UnknownLabel(l.clone())),
+            None if !labeled => return Err(SemanticErr::CantBreak(key.clone())),
+            _ => {}
+        }
+
+        let mut loops_left: usize = 0;
+
+        for (index, stack_op) in self.stack.iter().enumerate().rev() {
+            if let ScopeStackOp::EnterBreakable(name, value) = stack_op {
+                if is_target(name) {
+                    return Ok((index, loops_left))
+                }
+
+                //a loop expression can be in the middle of an expression,
+                //whose operands would be left on the stack
+                if !matches!(value, BreakValue::Nothing) {
+                    return Err(SemanticErr::CantBreak(key.clone()))
+                }
+
+                loops_left += 1;
+            }
+        }
+
+        unreachable!()
+    }
+
+    ///How many loops out the break goes, its value is cast to the loop's type
+    #[allow(clippy::result_large_err)]
+    pub fn break_target(&mut self, key: &Lexeme, label: &Option<Lexeme>, value: Option<&mut TypedExpr>) -> Result<usize, SemanticErr> {
+        let (index, loops_left) = self.find_breakable(key, label)?;
+
+        let break_value = match &mut self.stack[index] {
+            ScopeStackOp::EnterBreakable(_, t) => t,
+            _ => unreachable!()
+        };
+
+        let value_type = match &value {
+            Some(t) => t.final_type(),
+            None => ValueType::Void,
+        };
+
+        match break_value {
+            BreakValue::Nothing if value.is_some() => return Err(SemanticErr::WrongType(ValueType::Void, value_type, key.clone())),
+            BreakValue::Nothing => {}
+
+            BreakValue::Unknown => *break_value = BreakValue::Value(value_type),
+
+            BreakValue::Value(t) if *t != value_type => {
+                let cast = match value {
+                    Some(v) => v.try_implicit_cast(t),
+                    None => false,
+                };
+
+                if !cast {
+                    return Err(SemanticErr::WrongType(t.clone(), value_type, key.clone()))
+                }
+            }
+
+            BreakValue::Value(_) => {}
+        }
+
+        Ok(loops_left)
+    }
+
+    #[allow(clippy::result_large_err)]
+    pub fn continue_target(&self, key: &Lexeme, label: &Option<Lexeme>) -> Result<usize, SemanticErr> {
+        Ok(self.find_breakable(key, label)?.1)
+    }
+
+    ///Type of the innermost loop expression, given by its breaks so far
+    pub fn loop_expr_type(&self) -> ValueType {
         for stack_op in self.stack.iter().rev() {
-            if let ScopeStackOp::EnterBreakable = stack_op {
-                return true
+            if let ScopeStackOp::EnterBreakable(_, BreakValue::Value(t)) = stack_op {
+                return t.clone()
+            }
+
+            if let ScopeStackOp::EnterBreakable(..) = stack_op {
+                break
             }
         }
 
-        return false
+        ValueType::Void
     }
 
+    #[allow(clippy::result_large_err)]
     pub fn get_custom_type_from_name(&self, target: Lexeme) -> Result<CustomType, SemanticErr> {
         let target_name = target.data();
 
@@ -232,6 +353,17 @@ impl ScopeStack {
         self.stack.push(ScopeStackOp::DeclrVar(name, t));
     }
 
+    ///statics are declared before everything else, at the bottom of the stack
+    pub fn declare_static(&mut self, name: String, t: ValueType) {
+        self.global_used_ids.push(name.clone());
+        self.stack.push(ScopeStackOp::DeclrVar(name, t));
+    }
+
+    pub fn declare_const(&mut self, name: String, value: TypedExpr) {
+        self.global_used_ids.push(name.clone());
+        self.defined_consts.push((name, value));
+    }
+
     pub fn declare_custom_type(&mut self, t: CustomType) {
         self.global_used_ids.push(t.name());
         self.defined_types.push(t);
@@ -242,8 +374,12 @@ impl ScopeStack {
         self.defined_functions.push(template);
     }
 
-    pub fn enter_breakable(&mut self) {
-        self.stack.push(ScopeStackOp::EnterBreakable);
+    pub fn enter_breakable(&mut self, label: &Option<Lexeme>) {
+        self.stack.push(ScopeStackOp::EnterBreakable(label.as_ref().map(|x| x.data()), BreakValue::Nothing));
+    }
+
+    pub fn enter_loop_expr(&mut self, label: &Option<Lexeme>) {
+        self.stack.push(ScopeStackOp::EnterBreakable(label.as_ref().map(|x| x.data()), BreakValue::Unknown));
     }
 
     pub fn enter_returnable(&mut self, ret_type: ValueType) {
@@ -271,6 +407,7 @@ pub struct FnTemplate {
 
 
 impl StructTemplate {
+    #[allow(clippy::result_large_err)]
     pub fn check_recursive(&self, ss: &ScopeStack, iteration: u8) -> Result<(), SemanticErr> {
         if iteration == 100 {
             return Err(SemanticErr::RecursiveStruct(self.name.clone()))
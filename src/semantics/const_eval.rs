@@ -0,0 +1,203 @@
+use super::*;
+
+impl TypedExpr {
+    ///The value worked out at compile time, scalars become literals of their type
+    ///and aggregates literals of folded values
+    pub fn fold_const(&self) -> Option<TypedExpr> {
+        let final_type = self.final_type();
+
+        if final_type.is_primitive_type() || matches!(final_type, ValueType::CustomEnum(_)) {
+            return Some(scalar_expr(self.const_value()?, final_type))
+        }
+
+        let TypedExpr::Primary(_, primary) = self else {
+            return None
+        };
+
+        let folded = match &**primary {
+            TypedPrimaryExpr::Grouping(body) => return body.fold_const(),
+
+            TypedPrimaryExpr::StrLiteral(_) => return Some(self.clone()),
+
+            TypedPrimaryExpr::StructLiteral(t, fields) => {
+                let folded_fields = fields.iter()
+                    .map(|(offset, value)| Some((*offset, value.fold_const()?)))
+                    .collect::<Option<Vec<(u16, TypedExpr)>>>()?;
+
+                TypedPrimaryExpr::StructLiteral(t.clone(), folded_fields)
+            }
+
+            TypedPrimaryExpr::ArrayLiteral(item_type, items) => {
+                let folded_items = items.iter().map(|x| x.fold_const()).collect::<Option<Vec<TypedExpr>>>()?;
+                TypedPrimaryExpr::ArrayLiteral(item_type.clone(), folded_items)
+            }
+
+            TypedPrimaryExpr::ArrayRepeat(value, count) => TypedPrimaryExpr::ArrayRepeat(Box::new(value.fold_const()?), *count),
+
+            _ => return None
+        };
+
+        Some(TypedExpr::Primary(final_type, Box::new(folded)))
+    }
+
+    ///The value of a scalar known at compile time, pointers as u16 and enums as u8
+    pub fn const_value(&self) -> Option<NumLiteral> {
+        match self {
+            TypedExpr::Primary(_, primary) => match &**primary {
+                TypedPrimaryExpr::NumLiteral(n) => Some(n.clone()),
+                TypedPrimaryExpr::EnumVariant(_, (_, n)) => Some(NumLiteral::U8(*n)),
+                TypedPrimaryExpr::Grouping(body) => body.const_value(),
+
+                _ => None
+            },
+
+            TypedExpr::Cast(to_type, value) => NumLiteral::wrapping_from(value.const_value()?.value(), to_type),
+
+            TypedExpr::Term(t, binary_expr) | TypedExpr::Factor(t, binary_expr) => {
+                let x = binary_expr.left.const_value()?.value();
+                let y = binary_expr.right.const_value()?.value();
+
+                //division by a constant zero is rejected with DivByZero when the Factor is checked,
+                //the guards only keep unchecked trees from panicking
+                let result = match binary_expr.operator.as_str() {
+                    "+" => x.wrapping_add(y),
+                    "-" => x.wrapping_sub(y),
+                    "&" => x & y,
+                    "|" => x | y,
+                    "~|" => !(x | y),
+                    "^" => x ^ y,
+                    "*" => x.wrapping_mul(y),
+                    "/" if y != 0 => x / y,
+                    "%" if y != 0 => x % y,
+
+                    _ => return None
+                };
+
+                NumLiteral::wrapping_from(result, t)
+            }
+
+            TypedExpr::Shift(t, value, op, amount) => {
+                let x = value.const_value()?;
+                let count = amount.const_value()?.shift_count((x.size() * 8) as u8) as u32;
+
+                //signed values are sign extended, so >> is arithmetic for them
+                let result = match op.as_str() {
+                    "<<" => x.value() << count,
+                    _ => x.value() >> count,
+                };
+
+                NumLiteral::wrapping_from(result, t)
+            }
+
+            TypedExpr::Unary(t, op, value) => {
+                let x = value.const_value()?.value();
+
+                let result = match op.as_str() {
+                    "-" => x.wrapping_neg(),
+                    "~" => !x,
+                    _ => (x == 0) as i128,
+                };
+
+                NumLiteral::wrapping_from(result, t)
+            }
+
+            //operands have the same type, so their values compare like they do at runtime
+            TypedExpr::Equality(_, binary_expr) | TypedExpr::Comparison(_, binary_expr) => {
+                let x = binary_expr.left.const_value()?.value();
+                let y = binary_expr.right.const_value()?.value();
+
+                let result = match binary_expr.operator.as_str() {
+                    "==" => x == y,
+                    "!=" => x != y,
+                    "<" => x < y,
+                    ">" => x > y,
+                    "<=" => x <= y,
+                    ">=" => x >= y,
+                    "&&" => x != 0 && y != 0,
+                    _ => x != 0 || y != 0,
+                };
+
+                Some(NumLiteral::U8(result as u8))
+            }
+
+            TypedExpr::If(_, branches) => {
+                let (condition, t_value, f_value) = &**branches;
+
+                match condition.const_value()?.value() != 0 {
+                    true => t_value.const_value(),
+                    false => f_value.const_value(),
+                }
+            }
+
+            _ => None
+        }
+    }
+
+    ///The bytes of a folded value as laid out in memory
+    pub fn const_bytes(&self) -> Vec<u8> {
+        if let Some(value) = self.const_value() {
+            return (0..value.size()).map(|nth| value.get_nth_byte(nth)).collect()
+        }
+
+        let TypedExpr::Primary(_, primary) = self else {
+            unreachable!()
+        };
+
+        match &**primary {
+            TypedPrimaryExpr::Grouping(body) => body.const_bytes(),
+
+            TypedPrimaryExpr::StrLiteral(bytes) => bytes.clone(),
+
+            //fields are sorted by offset and have no gaps between them
+            TypedPrimaryExpr::StructLiteral(_, fields) => fields.iter().flat_map(|x| x.1.const_bytes()).collect(),
+
+            TypedPrimaryExpr::ArrayLiteral(_, items) => items.iter().flat_map(|x| x.const_bytes()).collect(),
+
+            TypedPrimaryExpr::ArrayRepeat(value, count) => value.const_bytes().repeat(*count as usize),
+
+            _ => unreachable!()
+        }
+    }
+}
+
+impl Expr {
+    ///The size of an array type or repeated array literal, an integer constant
+    #[allow(clippy::result_large_err)]
+    pub fn array_size(&self, ss: &ScopeStack) -> Result<u16, SemanticErr> {
+        let position = self.get_first_lexeme();
+
+        let mut size = self.clone();
+        size.neg_unary_literals().map_err(|_| SemanticErr::ArraySizeErr(position.clone()))?;
+
+        let mut typed_size = size.generate_typed_expr(ss)?;
+        typed_size.try_implicit_cast(&ValueType::U16);
+
+        if !typed_size.final_type().is_number_type() {
+            return Err(SemanticErr::WrongType(ValueType::U16, typed_size.final_type(), position))
+        }
+
+        match typed_size.const_value() {
+            Some(n) => u16::try_from(n.value()).map_err(|_| SemanticErr::ArraySizeErr(position)),
+            None => Err(SemanticErr::NotConstant(position)),
+        }
+    }
+}
+
+///A literal of `final_type` holding `value`
+fn scalar_expr(value: NumLiteral, final_type: ValueType) -> TypedExpr {
+    match &final_type {
+        ValueType::CustomEnum(template) => {
+            let variant = value.value() as u8;
+            let name = template.variants.iter().find(|x| x.1 == variant).map(|x| x.0.clone()).unwrap();
+
+            TypedExpr::Primary(final_type.clone(), Box::new(TypedPrimaryExpr::EnumVariant(template.clone(), (name, variant))))
+        }
+
+        ValueType::Pointer(_) => {
+            let address = TypedExpr::Primary(ValueType::U16, Box::new(TypedPrimaryExpr::NumLiteral(value)));
+            TypedExpr::Cast(final_type, Box::new(address))
+        }
+
+        _ => TypedExpr::Primary(final_type, Box::new(TypedPrimaryExpr::NumLiteral(value)))
+    }
+}
@@ -1,6 +1,7 @@
 use super::*;
 
 impl Expr {
+    #[allow(clippy::needless_return, clippy::result_large_err)]
     pub fn generate_typed_expr(&self, ss: &ScopeStack) -> Result<TypedExpr, SemanticErr> {
         match self {
             Expr::Primary(p) => {
@@ -10,12 +11,20 @@ impl Expr {
             }
 
             Expr::Cast(casted_expr, op_location, declr_to_type) => {
-                let to_type = ValueType::from_declr(declr_to_type, &ss.defined_types)?;
+                let to_type = ValueType::from_declr(declr_to_type, ss)?;
 
                 let typed_casted_expr = casted_expr.generate_typed_expr(ss)?;
+                let from_type = typed_casted_expr.final_type();
 
+                if !from_type.is_primitive_type() || !to_type.is_primitive_type() {
+                    return Err(SemanticErr::CantCast(op_location.clone()))
+                }
+
+                //pointers are 2 bytes wide, they only turn into u16 or other pointers
+                let is_pointer_or_u16 = |t: &ValueType| *t == ValueType::U16 || t.dereference().is_some();
 
-                if !typed_casted_expr.final_type().is_primitive_type() || !to_type.is_primitive_type() {
+                if (from_type.dereference().is_some() || to_type.dereference().is_some()) &&
+                !(is_pointer_or_u16(&from_type) && is_pointer_or_u16(&to_type)) {
                     return Err(SemanticErr::CantCast(op_location.clone()))
                 }
 
@@ -37,11 +46,71 @@ impl Expr {
             }
 
             Expr::Term(binary_expr) => {
-                let typed_binary_expr = binary_expr.generate_typed_binary_expr(ss)?;
+                let typed_left = binary_expr.left.generate_typed_expr(ss)?;
+
+                if let Some(pointed_type) = typed_left.final_type().dereference() {
+                    return binary_expr.generate_typed_pointer_term(typed_left, &pointed_type, ss)
+                }
+
+                let typed_binary_expr = binary_expr.generate_typed_binary_with_left(typed_left, ss)?;
 
                 return Ok(TypedExpr::Term(typed_binary_expr.left.final_type(), Box::new(typed_binary_expr)))
             }
 
+            Expr::Factor(binary_expr) => {
+                let typed_binary_expr = binary_expr.generate_typed_binary_expr(ss)?;
+
+                if !typed_binary_expr.left.final_type().is_number_type() {
+                    return Err(SemanticErr::CantOp(binary_expr.operator.clone()))
+                }
+
+                //only a divisor known at compile time, whatever the dividend
+                let divides_by_zero = matches!(binary_expr.operator.data().as_str(), "/" | "%")
+                    && typed_binary_expr.right.const_value().is_some_and(|x| x.value() == 0);
+
+                if divides_by_zero {
+                    return Err(SemanticErr::DivByZero(binary_expr.operator.clone()))
+                }
+
+                return Ok(TypedExpr::Factor(typed_binary_expr.left.final_type(), Box::new(typed_binary_expr)))
+            }
+
+            //a literal branch takes the type of the other one
+            Expr::If(_, branches) => {
+                let (cond, t_value, f_value) = &**branches;
+                let typed_cond = cond.generate_typed_expr(ss)?;
+
+                if typed_cond.final_type() != ValueType::U8 {
+                    return Err(SemanticErr::WrongType(ValueType::U8, typed_cond.final_type(), cond.get_first_lexeme()))
+                }
+
+                let mut typed_t_value = t_value.generate_typed_expr(ss)?;
+                let mut typed_f_value = f_value.generate_typed_expr(ss)?;
+
+                let t_type = typed_t_value.final_type();
+                let f_type = typed_f_value.final_type();
+
+                if t_type != f_type && !typed_f_value.try_implicit_cast(&t_type) && !typed_t_value.try_implicit_cast(&f_type) {
+                    return Err(SemanticErr::WrongType(t_type, f_type, f_value.get_first_lexeme()))
+                }
+
+                return Ok(TypedExpr::If(typed_t_value.final_type(), Box::new((typed_cond, typed_t_value, typed_f_value))))
+            }
+
+            //expressions only read the scope stack, the body works on a copy
+            //since nothing it declares outlives it and no break leaves it
+            Expr::Loop(_, label, body) => {
+                let mut body_ss = ss.clone();
+
+                body_ss.enter_scope();
+                body_ss.enter_loop_expr(label);
+                let typed_body = body.generate_typed_stmt(&mut body_ss, true)?;
+                let loop_type = body_ss.loop_expr_type();
+                body_ss.leave_scope();
+
+                return Ok(TypedExpr::Loop(loop_type, Box::new(typed_body)))
+            }
+
             Expr::Unary(op, expr) => {
                 let typed_expr = expr.generate_typed_expr(ss)?;
 
@@ -53,23 +122,49 @@ impl Expr {
             }
 
             Expr::Assign(assign) => {
+                let (typed_left, typed_right) = assign.generate_typed_assign(ss)?;
+
+                return Ok(TypedExpr::Assign(typed_left.final_type(), Box::new(typed_left), Box::new(typed_right)))
+            }
+
+            Expr::CompoundAssign(assign) => {
+                let operator = assign.operator.data();
+                let op = operator.trim_end_matches('=').to_string();
+
                 let typed_left = assign.left.generate_typed_expr(ss)?;
-                let mut typed_right = assign.right.generate_typed_expr(ss)?;
 
                 if !typed_left.is_assignable() {
                     return Err(SemanticErr::NotAVar(assign.operator.clone()))
                 }
 
                 let left_type = typed_left.final_type();
-                let right_type = typed_right.final_type();
 
-                if left_type != right_type {
-                    if !typed_right.try_implicit_cast(&left_type) {
-                        return Err(SemanticErr::WrongType(left_type, right_type, assign.operator.clone()))
+                let typed_right = match (op.as_str(), left_type.dereference()) {
+                    //the amount can be of any integer type, like in a Shift
+                    ("<<" | ">>", _) => {
+                        let typed_amount = assign.right.generate_typed_expr(ss)?;
+                        check_shift_amount(&typed_amount, &left_type, &assign.operator, ss)?;
+
+                        typed_amount
                     }
+
+                    //pointers move by whole items
+                    ("+" | "-", Some(pointed_type)) => pointer_offset(&assign.right, &pointed_type, ss)?,
+
+                    _ => assign.generate_typed_right_as(&left_type, ss)?
+                };
+
+                let can_op = match op.as_str() {
+                    "+" | "-" => left_type.is_primitive_type(),
+                    "&" | "|" | "^" => left_type.is_primitive_type() && left_type.dereference().is_none(),
+                    _ => left_type.is_number_type(),
+                };
+
+                if !can_op {
+                    return Err(SemanticErr::CantOp(assign.operator.clone()))
                 }
 
-                return Ok(TypedExpr::Assign(left_type, Box::new(typed_left), Box::new(typed_right)))
+                return Ok(TypedExpr::CompoundAssign(left_type, Box::new(typed_left), op, Box::new(typed_right)))
             }
         
             Expr::FnCall(fn_name, args) => {
@@ -78,7 +173,7 @@ impl Expr {
                     None => return Err(SemanticErr::UndeclaredFn(fn_name.clone()))
                 };
 
-                let typed_args = args.generate_typed_args(ss)?;
+                let mut typed_args = args.generate_typed_args(ss)?;
 
                 if fn_template.parameters.len() != typed_args.items.len() {
                     return Err(SemanticErr::FnArityErr(fn_name.clone()))
@@ -87,7 +182,7 @@ impl Expr {
                 for template_param in fn_template.parameters.iter().enumerate() {
                     let (param_n, param_type) = template_param;
 
-                    if typed_args.items[param_n].final_type() != *param_type {
+                    if typed_args.items[param_n].final_type() != *param_type && !typed_args.items[param_n].try_implicit_cast(param_type) {
                         return Err(SemanticErr::WrongType(param_type.clone(), typed_args.items[param_n].final_type(), fn_name.clone()))
                     }
                 }
@@ -95,40 +190,115 @@ impl Expr {
                 return Ok(TypedExpr::FnCall(fn_template.ret_type, fn_name.data(), typed_args))
             }
 
-            Expr::Shift(value, op, num) => {
-                let typed_value = value.generate_typed_expr(ss)?;
+            Expr::Shift(shift) => {
+                let typed_value = shift.left.generate_typed_expr(ss)?;
                 let value_type = typed_value.final_type();
                 
-                if !value_type.is_primitive_type() {
-                    return Err(SemanticErr::CantOp(op.clone()))
+                if !value_type.is_number_type() {
+                    return Err(SemanticErr::CantOp(shift.operator.clone()))
                 }
 
-                if let NumLiteral::U8(shift_amount) = num {
-                    if *shift_amount > (value_type.size(&ss.defined_types) * 8) as u8 { //size in bits
-                        return Err(SemanticErr::ShiftAmountErr(op.clone()))
-                    }
+                let typed_amount = shift.right.generate_typed_expr(ss)?;
+                check_shift_amount(&typed_amount, &value_type, &shift.operator, ss)?;
 
-                    return Ok(TypedExpr::Shift(value_type, Box::new(typed_value), op.data(), num.clone()))
-                } else {
-                    return Err(SemanticErr::WrongType(ValueType::U8, num.get_type(), op.clone()))
-                }
+                return Ok(TypedExpr::Shift(value_type, Box::new(typed_value), shift.operator.data(), Box::new(typed_amount)))
             }
         }
     }
 }
 
 impl PrimaryExpr {
+    #[allow(clippy::needless_return, clippy::result_large_err)]
     pub fn generate_typed_expr(&self, ss: &ScopeStack) -> Result<TypedPrimaryExpr, SemanticErr> {
         match self {
             PrimaryExpr::NumLiteral(num, _) => {
                 return Ok(TypedPrimaryExpr::NumLiteral(num.clone()))
             }
 
+            PrimaryExpr::StrLiteral(bytes, _) => {
+                return Ok(TypedPrimaryExpr::StrLiteral(bytes.clone()))
+            }
+
             PrimaryExpr::Grouping(body) => {
                 let typed_body = body.generate_typed_expr(ss)?;
                 return Ok(TypedPrimaryExpr::Grouping(typed_body))
             }
 
+            //every field is given once, in any order
+            PrimaryExpr::StructLiteral(name, fields) => {
+                let template = match ss.get_custom_type_from_name(name.clone())? {
+                    CustomType::CustomStruct(t) => t,
+                    _ => return Err(SemanticErr::NotAStruct(name.clone()))
+                };
+
+                let mut typed_fields: Vec<(u16, TypedExpr)> = vec![];
+
+                for (field, value) in fields {
+                    let (field_type, offset) = match template.get_field(field.data()) {
+                        Some(t) => t,
+                        None => return Err(SemanticErr::NoStructField(template, field.clone()))
+                    };
+
+                    if typed_fields.iter().any(|x| x.0 == offset) {
+                        return Err(SemanticErr::DuplicateField(field.clone()))
+                    }
+
+                    let mut typed_value = value.generate_typed_expr(ss)?;
+                    let value_type = typed_value.final_type();
+
+                    if value_type != field_type && !typed_value.try_implicit_cast(&field_type) {
+                        return Err(SemanticErr::WrongType(field_type, value_type, field.clone()))
+                    }
+
+                    typed_fields.push((offset, typed_value));
+                }
+
+                for (field_name, _, offset) in template.fields.iter() {
+                    if !typed_fields.iter().any(|x| x.0 == *offset) {
+                        return Err(SemanticErr::MissingField(field_name.clone(), name.clone()))
+                    }
+                }
+
+                //in memory order, so they can be pushed one after the other
+                typed_fields.sort_by_key(|x| x.0);
+
+                return Ok(TypedPrimaryExpr::StructLiteral(ValueType::CustomStruct(template.name), typed_fields))
+            }
+
+            //literal items take the type of the others
+            PrimaryExpr::ArrayLiteral(_, items) => {
+                let mut typed_items: Vec<TypedExpr> = vec![];
+
+                for item in items {
+                    let mut typed_item = item.generate_typed_expr(ss)?;
+
+                    if let Some(first) = typed_items.first() {
+                        let item_type = first.final_type();
+                        let new_type = typed_item.final_type();
+
+                        if new_type != item_type && !typed_item.try_implicit_cast(&item_type) {
+                            let mut cast_items = typed_items.clone();
+
+                            if !cast_items.iter_mut().all(|x| x.try_implicit_cast(&new_type)) {
+                                return Err(SemanticErr::WrongType(item_type, new_type, item.get_first_lexeme()))
+                            }
+
+                            typed_items = cast_items;
+                        }
+                    }
+
+                    typed_items.push(typed_item);
+                }
+
+                let item_type = typed_items[0].final_type();
+                return Ok(TypedPrimaryExpr::ArrayLiteral(item_type, typed_items))
+            }
+
+            PrimaryExpr::ArrayRepeat(_, value, count) => {
+                let typed_value = value.generate_typed_expr(ss)?;
+                return Ok(TypedPrimaryExpr::ArrayRepeat(Box::new(typed_value), count.array_size(ss)?))
+            }
+
             PrimaryExpr::EnumVariant(enum_name, enum_variant) => {
                 let enum_template = ss.get_custom_type_from_name(enum_name.clone())?;
 
@@ -146,45 +316,32 @@ impl PrimaryExpr {
             }
         
             PrimaryExpr::Variable(var) => {
+                //constants are folded where they are used
+                if let Variable::Id(id) = var {
+                    if let Some(value) = ss.get_const_from_name(id.data()) {
+                        return match value {
+                            TypedExpr::Primary(_, primary) => Ok(*primary),
+                            _ => Ok(TypedPrimaryExpr::Grouping(value)),
+                        }
+                    }
+                }
+
                 let typed_var = var.generate_typed_variable(ss, None)?;
                 return Ok(TypedPrimaryExpr::Variable(typed_var))
             }
 
-            PrimaryExpr::Ref(ref_op, var) => {
+            PrimaryExpr::Ref(_, var) => {
                 let typed_var = var.generate_typed_variable(ss, None)?;
                 let var_type = typed_var.final_type();
 
-                match ref_op.data().as_str() {
-                    "&" => {
-                        return Ok(TypedPrimaryExpr::Ref(
-                            ValueType::Pointer(Box::new(var_type)),
-                            ref_op.data(),
-                            typed_var))
-                    }
-
-                    "*" => {
-                        match var_type.dereference() {
-                            None => {
-                                return Err(SemanticErr::CantDeref(ref_op.clone()))
-                            }
-
-                            Some(derefed_type) => {
-                                return Ok(TypedPrimaryExpr::Ref(
-                                    derefed_type,
-                                    ref_op.data(),
-                                    typed_var))
-                            }
-                        }
-                    }
-
-                    _ => unreachable!()
-                }
+                return Ok(TypedPrimaryExpr::Ref(ValueType::Pointer(Box::new(var_type)), typed_var))
             }
         }
     }
 }
 
 impl Variable {
+    #[allow(clippy::collapsible_if, clippy::needless_return, clippy::result_large_err)]
     pub fn generate_typed_variable(&self, ss: &ScopeStack, parent: Option<StructTemplate>) -> Result<TypedVariable, SemanticErr> {
         match self {
             Variable::Id(id) => {
@@ -216,7 +373,14 @@ impl Variable {
             Variable::StructField(s) => {
                 let (head, tail) = &**s;
 
-                let typed_head = head.generate_typed_variable(ss, parent)?;
+                let mut typed_head = head.generate_typed_variable(ss, parent)?;
+
+                //fields are reached through any number of pointers
+                while let Some(pointed_type) = typed_head.final_type().dereference() {
+                    let pointer = TypedExpr::Primary(typed_head.final_type(), Box::new(TypedPrimaryExpr::Variable(typed_head)));
+                    typed_head = TypedVariable::Deref(pointed_type, Box::new(pointer));
+                }
+
                 let head_type = typed_head.final_type();
 
                 if let ValueType::CustomStruct(struct_name) = head_type {
@@ -257,14 +421,58 @@ impl Variable {
 
                 return Ok(TypedVariable::Array(head_item_type, Box::new(typed_array_head), typed_index))
             }
+
+            Variable::Deref(op, pointer) => {
+                let typed_pointer = pointer.generate_typed_expr(ss)?;
+
+                match typed_pointer.final_type().dereference() {
+                    Some(pointed_type) => return Ok(TypedVariable::Deref(pointed_type, Box::new(typed_pointer))),
+                    None => return Err(SemanticErr::CantDeref(op.clone()))
+                }
+            }
             
         }
     }
 }
 
 impl BinaryExpr {
+    ///Both sides of an assignment, the right one cast to the type of the left
+    #[allow(clippy::result_large_err)]
+    pub fn generate_typed_assign(&self, ss: &ScopeStack) -> Result<(TypedExpr, TypedExpr), SemanticErr> {
+        let typed_left = self.left.generate_typed_expr(ss)?;
+        let typed_right = self.generate_typed_right_as(&typed_left.final_type(), ss)?;
+
+        if !typed_left.is_assignable() {
+            return Err(SemanticErr::NotAVar(self.operator.clone()))
+        }
+
+        Ok((typed_left, typed_right))
+    }
+
+    ///the right side cast to `left_type`
+    #[allow(clippy::collapsible_if, clippy::result_large_err)]
+    fn generate_typed_right_as(&self, left_type: &ValueType, ss: &ScopeStack) -> Result<TypedExpr, SemanticErr> {
+        let mut typed_right = self.right.generate_typed_expr(ss)?;
+        let right_type = typed_right.final_type();
+
+        if *left_type != right_type {
+            if !typed_right.try_implicit_cast(left_type) {
+                return Err(SemanticErr::WrongType(left_type.clone(), right_type, self.operator.clone()))
+            }
+        }
+
+        Ok(typed_right)
+    }
+
+    #[allow(clippy::result_large_err)]
     pub fn generate_typed_binary_expr(&self, ss: &ScopeStack) -> Result<TypedBinaryExpr, SemanticErr> {
         let typed_left = self.left.generate_typed_expr(ss)?;
+        self.generate_typed_binary_with_left(typed_left, ss)
+    }
+
+    ///for when the left side was already checked
+    #[allow(clippy::collapsible_if, clippy::result_large_err)]
+    fn generate_typed_binary_with_left(&self, typed_left: TypedExpr, ss: &ScopeStack) -> Result<TypedBinaryExpr, SemanticErr> {
         let mut typed_right = self.right.generate_typed_expr(ss)?;
 
         let left_type = typed_left.final_type();
@@ -285,9 +493,100 @@ impl BinaryExpr {
             operator: self.operator.data(),
             right: typed_right })
     }
+
+    ///`ptr + n` and `ptr - n` move by `n` items, `ptr - ptr` is the distance in items as an i16
+    #[allow(clippy::result_large_err)]
+    fn generate_typed_pointer_term(&self, typed_left: TypedExpr, pointed_type: &ValueType, ss: &ScopeStack) -> Result<TypedExpr, SemanticErr> {
+        let pointer_type = typed_left.final_type();
+        let op = self.operator.data();
+
+        if op != "+" && op != "-" {
+            return Err(SemanticErr::CantOp(self.operator.clone()))
+        }
+
+        let typed_right = self.right.generate_typed_expr(ss)?;
+        let right_type = typed_right.final_type();
+
+        if right_type.dereference().is_none() {
+            let typed_offset = pointer_offset(&self.right, pointed_type, ss)?;
+            let binary = TypedBinaryExpr { left: typed_left, operator: op, right: typed_offset };
+
+            return Ok(TypedExpr::Term(pointer_type, Box::new(binary)))
+        }
+
+        if op != "-" {
+            return Err(SemanticErr::CantOp(self.operator.clone()))
+        }
+
+        if right_type != pointer_type {
+            return Err(SemanticErr::WrongType(pointer_type, right_type, self.operator.clone()))
+        }
+
+        let as_i16 = |x: TypedExpr| TypedExpr::Cast(ValueType::I16, Box::new(x));
+        let binary = TypedBinaryExpr { left: as_i16(typed_left), operator: op, right: as_i16(typed_right) };
+        let distance = TypedExpr::Term(ValueType::I16, Box::new(binary));
+
+        Ok(scale_by_size(distance, "/", pointed_type, ss))
+    }
+}
+
+///the byte offset of `n` items of `pointed_type`, `n` is a u16 or i16 like an array index
+#[allow(clippy::result_large_err)]
+fn pointer_offset(n: &Expr, pointed_type: &ValueType, ss: &ScopeStack) -> Result<TypedExpr, SemanticErr> {
+    let mut typed_n = n.generate_typed_expr(ss)?;
+    let n_type = typed_n.final_type();
+
+    if n_type != ValueType::U16 && n_type != ValueType::I16 &&
+    !typed_n.try_implicit_cast(&ValueType::U16) && !typed_n.try_implicit_cast(&ValueType::I16) {
+        return Err(SemanticErr::WrongType(ValueType::U16, n_type, n.get_first_lexeme()))
+    }
+
+    Ok(scale_by_size(typed_n, "*", pointed_type, ss))
+}
+
+///`value * size` or `value / size` in the type of `value`, which is 16 bits wide
+fn scale_by_size(value: TypedExpr, op: &str, pointed_type: &ValueType, ss: &ScopeStack) -> TypedExpr {
+    let size = pointed_type.size(&ss.defined_types);
+
+    if size == 1 {
+        return value
+    }
+
+    let value_type = value.final_type();
+    let size_literal = match value_type {
+        ValueType::I16 => NumLiteral::I16(size as i16),
+        _ => NumLiteral::U16(size),
+    };
+
+    let binary = TypedBinaryExpr {
+        left: value,
+        operator: op.to_string(),
+        right: TypedExpr::Primary(size_literal.get_type(), Box::new(TypedPrimaryExpr::NumLiteral(size_literal))),
+    };
+
+    TypedExpr::Factor(value_type, Box::new(binary))
+}
+
+///amounts are integers of any type, constant ones at most the size of the value in bits
+#[allow(clippy::result_large_err)]
+fn check_shift_amount(amount: &TypedExpr, value_type: &ValueType, op: &Lexeme, ss: &ScopeStack) -> Result<(), SemanticErr> {
+    if !amount.final_type().is_number_type() {
+        return Err(SemanticErr::WrongType(ValueType::U8, amount.final_type(), op.clone()))
+    }
+
+    if let Some(num) = amount.const_value() {
+        let bits = value_type.size(&ss.defined_types) as i128 * 8;
+
+        if num.value() < 0 || num.value() > bits {
+            return Err(SemanticErr::ShiftAmountErr(op.clone()))
+        }
+    }
+
+    Ok(())
 }
 
 impl Args {
+    #[allow(clippy::result_large_err)]
     pub fn generate_typed_args(&self, ss: &ScopeStack) -> Result<TypedArgs, SemanticErr> {
         let mut typed_items: Vec<TypedExpr> = vec![];
         
@@ -3,6 +3,8 @@ use super::{EnumTemplate, StructTemplate, CustomType, Lexeme, ValueType};
 #[derive(Debug)]
 pub enum SemanticErr {
     NoStructField(StructTemplate, Lexeme),
+    MissingField(String, Lexeme), //(FIELD, STRUCT LITERAL)
+    DuplicateField(Lexeme),
     NoEnumVariant(EnumTemplate, Lexeme),
     WrongAccess(CustomType, Lexeme),
     UnknownType(Lexeme),
@@ -19,9 +21,49 @@ pub enum SemanticErr {
     DuplicateParams(Lexeme),
     CantReturn(Lexeme),
     CantBreak(Lexeme),
+    UnknownLabel(Lexeme),
     EnumDuplicateVariants(Lexeme),
     RecursiveStruct(String),
     CantCast(Lexeme),
     CantOp(Lexeme),
     CantDeclareThisInLocalScope(Lexeme),
-}
\ No newline at end of file
+    NotConstant(Lexeme),
+    ArraySizeErr(Lexeme),
+    DivByZero(Lexeme),
+}
+
+impl SemanticErr {
+    ///Lexeme the error points at, if there is one
+    pub fn location(&self) -> Option<&Lexeme> {
+        match self {
+            Self::NoStructField(_, l) => Some(l),
+            Self::MissingField(_, l) => Some(l),
+            Self::DuplicateField(l) => Some(l),
+            Self::NoEnumVariant(_, l) => Some(l),
+            Self::WrongAccess(_, l) => Some(l),
+            Self::UnknownType(l) => Some(l),
+            Self::NotAStruct(l) => Some(l),
+            Self::NotAnArray(l) => Some(l),
+            Self::UndeclaredVar(l) => Some(l),
+            Self::WrongType(_, _, l) => Some(l),
+            Self::UsedId(l) => Some(l),
+            Self::CantDeref(l) => Some(l),
+            Self::NotAVar(l) => Some(l),
+            Self::ShiftAmountErr(l) => Some(l),
+            Self::UndeclaredFn(l) => Some(l),
+            Self::FnArityErr(l) => Some(l),
+            Self::DuplicateParams(l) => Some(l),
+            Self::CantReturn(l) => Some(l),
+            Self::CantBreak(l) => Some(l),
+            Self::UnknownLabel(l) => Some(l),
+            Self::EnumDuplicateVariants(l) => Some(l),
+            Self::RecursiveStruct(_) => None,
+            Self::CantCast(l) => Some(l),
+            Self::CantOp(l) => Some(l),
+            Self::CantDeclareThisInLocalScope(l) => Some(l),
+            Self::NotConstant(l) => Some(l),
+            Self::ArraySizeErr(l) => Some(l),
+            Self::DivByZero(l) => Some(l),
+        }
+    }
+}
@@ -1,6 +1,7 @@
 use super::*;
 
 impl Stmt {
+    #[allow(clippy::needless_return, clippy::result_large_err)]
     pub fn generate_typed_stmt(&self, ss: &mut ScopeStack, in_local_scope: bool) -> Result<TypedStmt, SemanticErr> {
         match self {
             Stmt::Block(body) => {
@@ -20,35 +21,52 @@ impl Stmt {
                     return Err(SemanticErr::UsedId(name.clone()))
                 }
 
-                let var_type = ValueType::from_declr(type_declr, &ss.defined_types)?;
+                let var_type = ValueType::from_declr(type_declr, ss)?;
 
-                let final_init_value: Option<TypedExpr>;
+                let final_init_value = match value {
+                    Some(init_expr) => Some(generate_typed_init(name, &var_type, init_expr, ss)?),
+                    None => None,
+                };
 
-                if let Some(init_expr) = value {
-                    let mut typed_init_expr = init_expr.generate_typed_expr(ss)?;
-                    let init_value_type = typed_init_expr.final_type();
+                ss.declare_var(name.data(), var_type.clone());
 
-                    if init_value_type != var_type {
-                        if !typed_init_expr.try_implicit_cast(&var_type) {
-                            return Err(SemanticErr::WrongType(var_type, init_value_type, name.clone()))
-                        }
-                    }
-                    
-                    final_init_value = Some(typed_init_expr);
-                } else {
-                    final_init_value = None;
+                return Ok(TypedStmt::VarDeclr(name.data(), var_type, final_init_value))
+            }
+
+            //the value is worked out at compile time, statics are stored as its bytes
+            //and constants are folded into every use
+            Stmt::StaticDeclr(name, type_declr, value) | Stmt::ConstDeclr(name, type_declr, value) => {
+                if in_local_scope {
+                    return Err(SemanticErr::CantDeclareThisInLocalScope(name.clone()))
                 }
 
-                ss.declare_var(name.data(), var_type.clone());
+                if ss.all_used_ids().contains(&name.data()) {
+                    return Err(SemanticErr::UsedId(name.clone()))
+                }
 
-                return Ok(TypedStmt::VarDeclr(name.data(), var_type, final_init_value))
+                let var_type = ValueType::from_declr(type_declr, ss)?;
+                let typed_value = generate_typed_init(name, &var_type, value, ss)?;
+
+                let folded = match typed_value.fold_const() {
+                    Some(t) => t,
+                    None => return Err(SemanticErr::NotConstant(value.get_first_lexeme()))
+                };
+
+                if let Stmt::ConstDeclr(..) = self {
+                    ss.declare_const(name.data(), folded.clone());
+                    return Ok(TypedStmt::ConstDeclr(name.data(), folded))
+                }
+
+                ss.declare_static(name.data(), var_type.clone());
+
+                return Ok(TypedStmt::StaticDeclr(name.data(), var_type, folded.const_bytes()))
             }
 
             Stmt::ExprStmt(expr) => {
                 return Ok(TypedStmt::ExprStmt(expr.generate_typed_expr(ss)?))
             }
 
-            Stmt::FnDeclr(fn_name, params, ret_type, body) => {
+            Stmt::FnDeclr(fn_name, params, ret_type, body, _) => {
                 if in_local_scope {
                     return Err(SemanticErr::CantDeclareThisInLocalScope(fn_name.clone()))
                 }
@@ -58,10 +76,17 @@ impl Stmt {
                 }
 
                 let typed_params = params.generate_typed_params(ss, None)?;
-                let typed_ret_type = ValueType::from_declr(ret_type, &ss.defined_types)?;
+                let typed_ret_type = ValueType::from_declr(ret_type, ss)?;
                 
+                //parameters live in their own scope, around the body's block
+                ss.enter_scope();
+                for (param_name, param_type) in typed_params.items.iter() {
+                    ss.declare_var(param_name.clone(), param_type.clone());
+                }
+
                 ss.enter_returnable(typed_ret_type.clone());
                 let typed_body = body.generate_typed_stmt(ss, true)?;
+                ss.leave_scope();
 
                 let fn_template = FnTemplate {
                     name: fn_name.data(),
@@ -71,12 +96,12 @@ impl Stmt {
 
                 ss.declare_fn(fn_template.clone());
 
-                return Ok(TypedStmt::FnDeclr(fn_template, Box::new(typed_body)))
+                return Ok(TypedStmt::FnDeclr(fn_template, typed_params, Box::new(typed_body)))
 
             }
 
             //struct declaration is done before
-            Stmt::StructDeclr(struct_name, _) => {
+            Stmt::StructDeclr(struct_name, _, _) => {
                 if in_local_scope {
                     return Err(SemanticErr::CantDeclareThisInLocalScope(struct_name.clone()))
                 }
@@ -107,12 +132,19 @@ impl Stmt {
                 return Ok(TypedStmt::ReturnStmt(typed_expr))
             }
 
-            Stmt::BreakStmt(op) => {
-                if !ss.check_if_breakable() {
-                    return Err(SemanticErr::CantBreak(op.clone()))
-                }
+            Stmt::BreakStmt(key, label, value) => {
+                let mut typed_value = match value {
+                    Some(t) => Some(t.generate_typed_expr(ss)?),
+                    None => None,
+                };
 
-                return Ok(TypedStmt::BreakStmt)
+                let loops_left = ss.break_target(key, label, typed_value.as_mut())?;
+
+                return Ok(TypedStmt::BreakStmt(loops_left, typed_value))
+            }
+
+            Stmt::ContinueStmt(key, label) => {
+                return Ok(TypedStmt::ContinueStmt(ss.continue_target(key, label)?))
             }
 
             Stmt::IfStmt(condition, t_branch, f_branch) => {
@@ -137,22 +169,83 @@ impl Stmt {
                 return Ok(TypedStmt::IfStmt(typed_condition, Box::new(typed_t_branch), typed_f_branch))
             }
 
-            Stmt::WhileStmt(cond, body) => {
+            Stmt::WhileStmt(label, cond, body) => {
                 let typed_cond = cond.generate_typed_expr(ss)?;
 
                 if typed_cond.final_type() != ValueType::U8 {
                     return Err(SemanticErr::WrongType(ValueType::U8, typed_cond.final_type(), cond.get_first_lexeme()))
                 }
 
-                ss.enter_breakable();
+                ss.enter_scope();
+                ss.enter_breakable(label);
                 let typed_body = body.generate_typed_stmt(ss, true)?;
+                ss.leave_scope();
 
                 return Ok(TypedStmt::WhileStmt(typed_cond, Box::new(typed_body)))
             }
 
-            Stmt::LoopStmt(body) => {
-                ss.enter_breakable();
+            Stmt::ForStmt(label, var, type_declr, header, body) => {
+                let (iterated, range_end) = &**header;
+
+                if ss.global_used_ids.contains(&var.data()) {
+                    return Err(SemanticErr::UsedId(var.clone()))
+                }
+
+                let declared_type = match type_declr {
+                    Some(t) => Some(ValueType::from_declr(t, ss)?),
+                    None => None,
+                };
+
+                let typed_stmt = match range_end {
+                    Some((op, end)) => {
+                        let (var_type, typed_start, typed_end) = generate_typed_range(iterated, op, end, declared_type, ss)?;
+
+                        ss.enter_scope();
+                        ss.enter_breakable(label);
+                        ss.declare_var(var.data(), var_type.clone());
+                        let typed_body = body.generate_typed_stmt(ss, true)?;
+                        ss.leave_scope();
+
+                        TypedStmt::ForRange(var.data(), var_type, typed_start, typed_end, op.data() == "..=", Box::new(typed_body))
+                    }
+
+                    None => {
+                        //the items are copied out of an array variable, whose length is known
+                        let typed_array = iterated.generate_typed_expr(ss)?;
+
+                        let (array, item_type, len) = match (typed_array.final_type(), typed_array) {
+                            (ValueType::Array(item_type, len), TypedExpr::Primary(_, primary)) => match *primary {
+                                TypedPrimaryExpr::Variable(array) => (array, *item_type, len),
+                                _ => return Err(SemanticErr::NotAnArray(iterated.get_first_lexeme()))
+                            },
+
+                            _ => return Err(SemanticErr::NotAnArray(iterated.get_first_lexeme()))
+                        };
+
+                        if let Some(t) = declared_type {
+                            if t != item_type {
+                                return Err(SemanticErr::WrongType(t, item_type, var.clone()))
+                            }
+                        }
+
+                        ss.enter_scope();
+                        ss.enter_breakable(label);
+                        ss.declare_var(var.data(), item_type.clone());
+                        let typed_body = body.generate_typed_stmt(ss, true)?;
+                        ss.leave_scope();
+
+                        TypedStmt::ForEach(var.data(), item_type, array, len, Box::new(typed_body))
+                    }
+                };
+
+                return Ok(typed_stmt)
+            }
+
+            Stmt::LoopStmt(label, body) => {
+                ss.enter_scope();
+                ss.enter_breakable(label);
                 let typed_body = body.generate_typed_stmt(ss, true)?;
+                ss.leave_scope();
 
                 return Ok(TypedStmt::LoopStmt(Box::new(typed_body)))
             }
@@ -162,6 +255,7 @@ impl Stmt {
 }
 
 impl Parameters {
+    #[allow(clippy::needless_borrow, clippy::result_large_err)]
     pub fn generate_typed_params(&self, ss: &ScopeStack, being_defined: Option<&Vec<String>>) -> Result<TypedParameters, SemanticErr> {
         let mut used_param_names: Vec<String> = vec![];
         let mut typed_params: Vec<(String, ValueType)> = vec![];
@@ -175,9 +269,9 @@ impl Parameters {
             used_param_names.push(param_name.data());
 
             let param_type = match being_defined {
-                Some(t) => ValueType::from_declr_new_struct(&type_declr, &ss.defined_types, t)?,
+                Some(t) => ValueType::from_declr_new_struct(&type_declr, ss, t)?,
 
-                None => ValueType::from_declr(&type_declr, &ss.defined_types)?
+                None => ValueType::from_declr(&type_declr, ss)?
             };
                 
 
@@ -186,4 +280,44 @@ impl Parameters {
         
         Ok(TypedParameters {items: typed_params})
     }
-}
\ No newline at end of file
+}
+
+///The initial value of a variable, cast to its type
+#[allow(clippy::result_large_err)]
+fn generate_typed_init(name: &Lexeme, var_type: &ValueType, init_expr: &Expr, ss: &ScopeStack) -> Result<TypedExpr, SemanticErr> {
+    let mut typed_init_expr = init_expr.generate_typed_expr(ss)?;
+    let init_value_type = typed_init_expr.final_type();
+
+    if init_value_type != *var_type && !typed_init_expr.try_implicit_cast(var_type) {
+        return Err(SemanticErr::WrongType(var_type.clone(), init_value_type, name.clone()))
+    }
+
+    Ok(typed_init_expr)
+}
+
+///The type of the loop variable is the declared one, or the one the bounds share
+#[allow(clippy::result_large_err)]
+fn generate_typed_range(start: &Expr, op: &Lexeme, end: &Expr, declared_type: Option<ValueType>, ss: &ScopeStack) -> Result<(ValueType, TypedExpr, TypedExpr), SemanticErr> {
+    let mut typed_start = start.generate_typed_expr(ss)?;
+    let mut typed_end = end.generate_typed_expr(ss)?;
+
+    let var_type = match declared_type {
+        Some(t) => t,
+        None if typed_start.final_type() != typed_end.final_type() && typed_start.try_implicit_cast(&typed_end.final_type()) => typed_end.final_type(),
+        None => typed_start.final_type(),
+    };
+
+    if !var_type.is_number_type() {
+        return Err(SemanticErr::CantOp(op.clone()))
+    }
+
+    for (bound, typed_bound) in [(start, &mut typed_start), (end, &mut typed_end)] {
+        let bound_type = typed_bound.final_type();
+
+        if bound_type != var_type && !typed_bound.try_implicit_cast(&var_type) {
+            return Err(SemanticErr::WrongType(var_type, bound_type, bound.get_first_lexeme()))
+        }
+    }
+
+    Ok((var_type, typed_start, typed_end))
+}
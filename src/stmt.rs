@@ -6,24 +6,29 @@ use std::fmt::Debug;
 #[derive(Debug, Clone)]
 pub enum Stmt {
     VarDeclr(Lexeme, TypeDeclr, Option<Expr>),
-    FnDeclr(Lexeme, Parameters, TypeDeclr, Box<Stmt>),
-    StructDeclr(Lexeme, Parameters),
+    StaticDeclr(Lexeme, TypeDeclr, Expr),
+    ConstDeclr(Lexeme, TypeDeclr, Expr),
+    FnDeclr(Lexeme, Parameters, TypeDeclr, Box<Stmt>, Option<String>), //last is the doc comment
+    StructDeclr(Lexeme, Parameters, Option<String>),
     EnumDeclr(Lexeme, Vec<Lexeme>),
 
     ExprStmt(Expr),
-    LoopStmt(Box<Stmt>),
-    WhileStmt(Expr, Box<Stmt>),
+    //loops start with their optional label
+    LoopStmt(Option<Lexeme>, Box<Stmt>),
+    WhileStmt(Option<Lexeme>, Expr, Box<Stmt>),
+    ForStmt(Option<Lexeme>, Lexeme, Option<TypeDeclr>, Box<(Expr, Option<(Lexeme, Expr)>)>, Box<Stmt>), //(LABEL, VAR, TYPE, (START OR ARRAY, (RANGE OPERATOR, END)), BODY)
     IfStmt(Expr, Box<Stmt>, Option<Box<Stmt>>),
-    BreakStmt(Lexeme),
+    BreakStmt(Lexeme, Option<Lexeme>, Option<Expr>), //(KEYWORD, LABEL, VALUE)
+    ContinueStmt(Lexeme, Option<Lexeme>), //(KEYWORD, LABEL)
     ReturnStmt(Lexeme, Expr),
     Block(Vec<Stmt>),
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone)]
 pub enum TypeDeclr {
     Basic(Lexeme),
     Pointer(Box<TypeDeclr>),
-    Array(Box<TypeDeclr>, u16),
+    Array(Box<TypeDeclr>, Box<Expr>), //(ITEM TYPE, SIZE)
 }
 
 #[derive(Debug, Clone)]
@@ -32,6 +37,7 @@ pub struct Parameters {
 }
 
 impl Stmt {
+    #[allow(clippy::collapsible_match)]
     pub fn neg_unary_literals(&mut self) -> Result<(), Lexeme>{
         match self {
             Self::ExprStmt(e) => e.neg_unary_literals()?,
@@ -42,7 +48,7 @@ impl Stmt {
                 }
             }
             
-            Self::FnDeclr(_,_ ,_ , body) => body.neg_unary_literals()?,
+            Self::FnDeclr(_,_ ,_ , body, _) => body.neg_unary_literals()?,
 
             Self::IfStmt(cond, t_branch, f_branch) => {
                 cond.neg_unary_literals()?;
@@ -52,7 +58,9 @@ impl Stmt {
                 }
             }
 
-            Self::LoopStmt(b) => b.neg_unary_literals()?,
+            Self::LoopStmt(_, b) => b.neg_unary_literals()?,
+
+            Self::BreakStmt(_, _, Some(e)) => e.neg_unary_literals()?,
 
             Self::ReturnStmt(_, e) => e.neg_unary_literals()?,
 
@@ -62,10 +70,21 @@ impl Stmt {
                 }
             }
 
-            Self::WhileStmt(cond, body) => {
+            Self::StaticDeclr(_, _, v) | Self::ConstDeclr(_, _, v) => v.neg_unary_literals()?,
+
+            Self::WhileStmt(_, cond, body) => {
                 cond.neg_unary_literals()?;
                 body.neg_unary_literals()?;
             }
+
+            Self::ForStmt(_, _, _, header, body) => {
+                let (iterated, range_end) = &mut **header;
+                iterated.neg_unary_literals()?;
+                if let Some((_, end)) = range_end {
+                    end.neg_unary_literals()?;
+                }
+                body.neg_unary_literals()?;
+            }
             
             _ => {}
         }
@@ -5,6 +5,8 @@ pub enum Token {
     Cond(String),
     Id(String),
     Lit(String),
+    Str(String),
+    Label(String),
     ParenOpen,
     ParenClose,
     SquareOpen,
@@ -16,6 +18,7 @@ pub enum Token {
     Comma,
     Period,
     Arrow,
+    DocComment(String),
     EOF,
 }
 
@@ -30,6 +33,7 @@ pub struct Lexeme {
 }
 
 impl Lexeme {
+    #[allow(clippy::redundant_field_names)]
     pub fn new(line: usize, col: usize, tok: Token) -> Self {
         Self {
             line: line,
@@ -45,6 +49,9 @@ impl Lexeme {
             Token::Cond(s) => s,
             Token::Id(s) => s,
             Token::Lit(s) => s,
+            Token::Str(s) => s,
+            Token::Label(s) => s,
+            Token::DocComment(s) => s,
             _ => panic!("No data for this token")
         }
     }
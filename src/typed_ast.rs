@@ -6,13 +6,17 @@ use crate::types::*;
 #[derive(Debug, Clone)]
 pub enum TypedExpr {
     Assign(ValueType, Box<TypedExpr>, Box<TypedExpr>),
+    CompoundAssign(ValueType, Box<TypedExpr>, String, Box<TypedExpr>), //the operator without the '='
     Equality(ValueType, Box<TypedBinaryExpr>),
     Comparison(ValueType, Box<TypedBinaryExpr>),
     Term(ValueType, Box<TypedBinaryExpr>),
-    Shift(ValueType, Box<TypedExpr>, String, NumLiteral),
+    Factor(ValueType, Box<TypedBinaryExpr>),
+    Shift(ValueType, Box<TypedExpr>, String, Box<TypedExpr>), //(TYPE, VALUE, OPERATOR, AMOUNT)
     Unary(ValueType, String, Box<TypedExpr>),
     Cast(ValueType, Box<TypedExpr>),
     FnCall(ValueType, String, TypedArgs),
+    If(ValueType, Box<(TypedExpr, TypedExpr, TypedExpr)>), //(TYPE, (CONDITION, TRUE VALUE, FALSE VALUE))
+    Loop(ValueType, Box<TypedStmt>),
     Primary(ValueType, Box<TypedPrimaryExpr>),
 }
 
@@ -20,13 +24,17 @@ impl TypedExpr {
     pub fn final_type(&self) -> ValueType {
         match self {
             TypedExpr::Assign(t, _, _) => t.clone(),
+            TypedExpr::CompoundAssign(t, _, _, _) => t.clone(),
             TypedExpr::Cast(t, _) => t.clone(),
             TypedExpr::Comparison(t, _) => t.clone(),
             TypedExpr::Equality(t, _) => t.clone(),
             TypedExpr::FnCall(t, _, _) => t.clone(),
             TypedExpr::Shift(t, _, _, _) => t.clone(),
             TypedExpr::Term(t, _) => t.clone(),
+            TypedExpr::Factor(t, _) => t.clone(),
             TypedExpr::Unary(t, _, _) => t.clone(),
+            TypedExpr::If(t, _) => t.clone(),
+            TypedExpr::Loop(t, _) => t.clone(),
 
             TypedExpr::Primary(t, _) => t.clone(),
         }
@@ -34,22 +42,11 @@ impl TypedExpr {
 
     ///ensures that expressions such as
     ///1 = 2 or &x = 5 are illegal
-    ///but x[1].y are
+    ///but x[1].y and *p are
     pub fn is_assignable(&self) -> bool {
         match self {
             TypedExpr::Primary(_, primary_expr) => {
-                match &**primary_expr {
-                    TypedPrimaryExpr::Variable(_) => true,
-                    TypedPrimaryExpr::Ref(_, ref_op, _) => {
-                        if ref_op.as_str() == "*" {
-                            true
-                        } else {
-                            false
-                        }
-                    }
-
-                    _ => false
-                }
+                matches!(&**primary_expr, TypedPrimaryExpr::Variable(_))
             }
 
             TypedExpr::Assign(_, assign_left, _) => {
@@ -61,6 +58,55 @@ impl TypedExpr {
     }
 
     pub fn try_implicit_cast(&mut self, ty: &ValueType) -> bool {
+        //both branches have to be cast, or neither is
+        if let TypedExpr::If(final_type, branches) = self {
+            let mut cast_branches = branches.clone();
+
+            if cast_branches.1.try_implicit_cast(ty) && cast_branches.2.try_implicit_cast(ty) {
+                *branches = cast_branches;
+                *final_type = ty.clone();
+                return true
+            }
+
+            return false
+        }
+
+        //array literals are cast item by item, all or none of them
+        if let (TypedExpr::Primary(final_type, primary), ValueType::Array(to_item_type, len)) = (&mut *self, ty) {
+            let mut cast_primary = (**primary).clone();
+
+            let cast = match &mut cast_primary {
+                TypedPrimaryExpr::ArrayLiteral(item_type, items) if items.len() == *len as usize => {
+                    *item_type = (**to_item_type).clone();
+                    items.iter_mut().all(|x| x.final_type() == **to_item_type || x.try_implicit_cast(to_item_type))
+                }
+
+                TypedPrimaryExpr::ArrayRepeat(value, count) if count == len => {
+                    value.final_type() == **to_item_type || value.try_implicit_cast(to_item_type)
+                }
+
+                _ => false
+            };
+
+            if cast {
+                **primary = cast_primary;
+                *final_type = ty.clone();
+                return true
+            }
+        }
+
+        //strings can be used as a pointer to their first byte
+        if let TypedExpr::Primary(final_type, primary) = self {
+            if let TypedPrimaryExpr::StrLiteral(_) = &**primary {
+                if *ty == ValueType::Pointer(Box::new(ValueType::U8)) {
+                    *final_type = ty.clone();
+                    return true
+                }
+
+                return false
+            }
+        }
+
         if let TypedExpr::Primary(final_type, primary) = self {
             if let TypedPrimaryExpr::NumLiteral(original_num_literal) = &mut **primary {
                 match original_num_literal.try_implicit_cast(ty) {
@@ -84,9 +130,13 @@ impl TypedExpr {
 pub enum TypedPrimaryExpr {
     Grouping(TypedExpr),
     NumLiteral(NumLiteral),
+    StrLiteral(Vec<u8>),
+    StructLiteral(ValueType, Vec<(u16, TypedExpr)>), //(TYPE, (FIELD OFFSET, VALUE)), by offset
+    ArrayLiteral(ValueType, Vec<TypedExpr>), //(ITEM TYPE, ITEMS)
+    ArrayRepeat(Box<TypedExpr>, u16), //(VALUE, COUNT)
     Variable(TypedVariable),
     EnumVariant(EnumTemplate, (String, u8,)), //Enum Template, (Enum Variant, Variant Number)
-    Ref(ValueType, String, TypedVariable),
+    Ref(ValueType, TypedVariable), //address of the variable
 }
 
 impl TypedPrimaryExpr {
@@ -100,9 +150,17 @@ impl TypedPrimaryExpr {
 
             TypedPrimaryExpr::NumLiteral(n) => n.get_type(),
 
+            TypedPrimaryExpr::StrLiteral(bytes) => ValueType::Array(Box::new(ValueType::U8), bytes.len() as u16),
+
+            TypedPrimaryExpr::StructLiteral(t, _) => t.clone(),
+
+            TypedPrimaryExpr::ArrayLiteral(item_type, items) => ValueType::Array(Box::new(item_type.clone()), items.len() as u16),
+
+            TypedPrimaryExpr::ArrayRepeat(value, count) => ValueType::Array(Box::new(value.final_type()), *count),
+
             TypedPrimaryExpr::Variable(v) => v.final_type(),
 
-            TypedPrimaryExpr::Ref(t, _, _) => {
+            TypedPrimaryExpr::Ref(t, _) => {
                 t.clone()
             }
         }
@@ -115,6 +173,7 @@ pub enum TypedVariable {
     Id(ValueType, String, u16),
     StructField(ValueType, Box<(TypedVariable, TypedVariable)>),
     Array(ValueType, Box<TypedVariable>, TypedExpr),
+    Deref(ValueType, Box<TypedExpr>), //(POINTED TO TYPE, POINTER)
 }
 
 impl TypedVariable {
@@ -123,6 +182,7 @@ impl TypedVariable {
             TypedVariable::Id(t, _, _) => t.clone(),
             TypedVariable::Array(t, _, _) => t.clone(),
             TypedVariable::StructField(t, _) => t.clone(),
+            TypedVariable::Deref(t, _) => t.clone(),
         }
     }
 }
@@ -143,14 +203,19 @@ pub struct TypedArgs {
 #[derive(Debug, Clone)]
 pub enum TypedStmt {
     VarDeclr(String, ValueType, Option<TypedExpr>),
-    FnDeclr(FnTemplate, Box<TypedStmt>),
+    StaticDeclr(String, ValueType, Vec<u8>), //(NAME, TYPE, INITIAL BYTES)
+    ConstDeclr(String, TypedExpr), //the value is folded into every use
+    FnDeclr(FnTemplate, TypedParameters, Box<TypedStmt>),
     CustomTypeDeclr,
 
     ExprStmt(TypedExpr),
     LoopStmt(Box<TypedStmt>),
     WhileStmt(TypedExpr, Box<TypedStmt>),
+    ForRange(String, ValueType, TypedExpr, TypedExpr, bool, Box<TypedStmt>), //(VAR, TYPE, START, END, INCLUSIVE, BODY)
+    ForEach(String, ValueType, TypedVariable, u16, Box<TypedStmt>), //(VAR, ITEM TYPE, ARRAY, LENGTH, BODY)
     IfStmt(TypedExpr, Box<TypedStmt>, Option<Box<TypedStmt>>),
-    BreakStmt,
+    BreakStmt(usize, Option<TypedExpr>), //(LOOPS LEFT BEFORE THE TARGET, VALUE)
+    ContinueStmt(usize),
     ReturnStmt(TypedExpr),
     Block(Vec<TypedStmt>),
 }
@@ -109,7 +109,8 @@ pub fn get_custom_struct(target_name: String, defined_types: &Vec<CustomType>) -
 }
 
 impl ValueType {
-    pub fn from_declr(declr: &TypeDeclr, defined_types: &Vec<CustomType>) -> Result<ValueType, SemanticErr> {
+    #[allow(clippy::needless_return, clippy::result_large_err)]
+    pub fn from_declr(declr: &TypeDeclr, ss: &ScopeStack) -> Result<ValueType, SemanticErr> {
         match declr {
             TypeDeclr::Basic(lex) => {
                 let type_text = lex.data();
@@ -132,7 +133,7 @@ impl ValueType {
                     _ => {}
                 }
 
-                for t in defined_types {
+                for t in &ss.defined_types {
                     match &t {
                         CustomType::CustomEnum(e) => {
                             if lex.data() == e.name {
@@ -154,23 +155,24 @@ impl ValueType {
             }
 
             TypeDeclr::Pointer(p) => {
-                let points_to = ValueType::from_declr(p, defined_types)?;
+                let points_to = ValueType::from_declr(p, ss)?;
                 return Ok(ValueType::Pointer(Box::new(points_to)))
             }
 
             TypeDeclr::Array(item_t, size) => {
-                let item_type = ValueType::from_declr(item_t, defined_types)?;
-                return Ok(ValueType::Array(Box::new(item_type), *size))
+                let item_type = ValueType::from_declr(item_t, ss)?;
+                return Ok(ValueType::Array(Box::new(item_type), size.array_size(ss)?))
             }
         }
     }
 
     ///being_defined is for structs that may not be known/legal yet,
     ///but we want to assume they're valid
-    pub fn from_declr_new_struct(declr: &TypeDeclr, defined_types: &Vec<CustomType>, being_defined: &Vec<String>) -> Result<ValueType, SemanticErr> {
+    #[allow(clippy::result_large_err)]
+    pub fn from_declr_new_struct(declr: &TypeDeclr, ss: &ScopeStack, being_defined: &Vec<String>) -> Result<ValueType, SemanticErr> {
         match declr {
             TypeDeclr::Basic(id) => {
-                if let Ok(t) = ValueType::from_declr(declr, defined_types) {
+                if let Ok(t) = ValueType::from_declr(declr, ss) {
                     return Ok(t)
                 }
                 
@@ -185,14 +187,14 @@ impl ValueType {
 
             TypeDeclr::Pointer(p) => Ok(
                 ValueType::Pointer(
-                    Box::new(ValueType::from_declr_new_struct(p, defined_types, being_defined)?)
+                    Box::new(ValueType::from_declr_new_struct(p, ss, being_defined)?)
                 )
             ),
 
             TypeDeclr::Array(item_type, size) => Ok(
                 ValueType::Array(
-                    Box::new(ValueType::from_declr_new_struct(item_type, defined_types, being_defined)?),
-                    *size)
+                    Box::new(ValueType::from_declr_new_struct(item_type, ss, being_defined)?),
+                    size.array_size(ss)?)
             )
         }
     }
@@ -242,6 +244,7 @@ impl ValueType {
         None
     }
 
+    #[allow(clippy::match_like_matches_macro)]
     pub fn is_primitive_type(&self) -> bool {
         match self {
             ValueType::Array(_, _) => false,
@@ -253,6 +256,16 @@ impl ValueType {
         }
     }
 
+    ///integers, the operands of * / and %
+    pub fn is_number_type(&self) -> bool {
+        match self {
+            ValueType::Pointer(_) => false,
+
+            _ => self.is_primitive_type(),
+        }
+    }
+
+    #[allow(clippy::match_like_matches_macro)]
     pub fn is_signed_type(&self) -> bool {
         match self {
             ValueType::I8 => true,
@@ -0,0 +1,128 @@
+//! Golden tests over the programs in `tests/golden/`.
+//!
+//! Every `NAME.mycc` goes through the whole pipeline and is checked against
+//! the expectation files next to it:
+//! - `NAME.err`: the diagnostics the compiler reports
+//! - `NAME.asm`: the emitted assembly
+//! - `NAME.out`: port output and top level variables after running
+//!
+//! Run with `MYCC_BLESS=1` to write the actual results as the new
+//! expectations. A program without any expectation file gets `.err` or
+//! `.out` when blessed, depending on whether it compiles. Assembly is only
+//! pinned for programs that already have a `.asm`, an empty one will do.
+
+use mycc::emulator::{Emulator, DEFAULT_MAX_STEPS};
+use mycc::{compile, Artifacts, CompileOptions, Diagnostics, Emit};
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const GOLDEN_DIR: &str = "tests/golden";
+
+fn blessing() -> bool {
+    std::env::var_os("MYCC_BLESS").is_some()
+}
+
+fn render_diagnostics(diagnostics: &Diagnostics) -> String {
+    format!("{}\n", diagnostics)
+}
+
+///port output followed by the top level variables
+fn render_run(artifacts: &Artifacts) -> String {
+    let mut emu = Emulator::new(artifacts.bin.clone().expect("compiled to a binary"));
+    let mut out = String::new();
+
+    if let Err(e) = emu.run(DEFAULT_MAX_STEPS) {
+        out.push_str(&format!("Runtime Error: {}\n", e));
+    }
+
+    for (port, value) in &emu.output {
+        out.push_str(&format!("Out Port {}: {}\n", port, value));
+    }
+
+    out.push_str(&artifacts.format_globals(&emu));
+    out
+}
+
+///Returns a description of every mismatch
+fn check_program(source_path: &Path) -> Vec<String> {
+    let source = fs::read_to_string(source_path).unwrap();
+    let result = compile(&source, &CompileOptions { emit: Emit::Bin });
+
+    let mut actual: Vec<(&str, String)> = vec![];
+
+    match &result {
+        Ok(artifacts) => {
+            let asm = artifacts.output(Emit::Asm).unwrap();
+            actual.push(("asm", String::from_utf8(asm).unwrap()));
+            actual.push(("out", render_run(artifacts)));
+        }
+
+        Err(diagnostics) => actual.push(("err", render_diagnostics(diagnostics))),
+    }
+
+    let expectation = |ext: &str| source_path.with_extension(ext);
+    let has_expectation = ["err", "asm", "out"].iter().any(|x| expectation(x).exists());
+
+    let mut failures: Vec<String> = vec![];
+
+    for ext in ["err", "asm", "out"] {
+        let path = expectation(ext);
+        let actual_text = actual.iter().find(|x| x.0 == ext).map(|x| x.1.clone());
+
+        if blessing() {
+            match &actual_text {
+                //assembly is only pinned for programs that ask for it
+                Some(text) if path.exists() || (!has_expectation && ext != "asm") => {
+                    fs::write(&path, text).unwrap();
+                }
+
+                None if path.exists() => fs::remove_file(&path).unwrap(),
+
+                _ => {}
+            }
+
+            continue
+        }
+
+        if !path.exists() {
+            continue
+        }
+
+        let expected = fs::read_to_string(&path).unwrap();
+
+        match actual_text {
+            Some(text) if text == expected => {}
+
+            Some(text) => failures.push(format!(
+                "{}: mismatch\n--- expected\n{}--- actual\n{}", path.display(), expected, text)),
+
+            None => failures.push(format!(
+                "{}: expected {} but the program {}", path.display(), ext,
+                if result.is_ok() { "compiled" } else { "did not compile" })),
+        }
+    }
+
+    if !has_expectation && !blessing() {
+        failures.push(format!("{}: no expectation, run with MYCC_BLESS=1", source_path.display()));
+    }
+
+    failures
+}
+
+#[test]
+fn golden_programs() {
+    let mut programs: Vec<PathBuf> = fs::read_dir(GOLDEN_DIR).unwrap()
+        .map(|x| x.unwrap().path())
+        .filter(|x| x.extension().is_some_and(|e| e == "mycc"))
+        .collect();
+    programs.sort();
+
+    assert!(!programs.is_empty(), "no programs in {}", GOLDEN_DIR);
+
+    let failures: Vec<String> = programs.iter().flat_map(|x| check_program(x)).collect();
+
+    if !failures.is_empty() {
+        panic!("{} golden failure(s):\n\n{}", failures.len(), failures.join("\n"));
+    }
+}
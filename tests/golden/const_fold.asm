@@ -0,0 +1,63 @@
+_start:
+    imr R0 2
+    imr MARL 0
+    imr MARH 1
+    str R0
+    imr BPL 0
+    imr BPH 64
+    imr SPL 1
+    imr SPH 64
+    imr MARL 0
+    imr MARH 1
+    ld R0
+    imr MARL 0
+    imr MARH 63
+    str R0
+    imr R0 5
+    imr MARL 8
+    imr MARH 63
+    str R0
+    clc
+    imr MARL 0
+    imr MARH 63
+    ld R0
+    imr MARL 8
+    imr MARH 63
+    ld R1
+    rmov R0
+    adc R1
+    amov R0
+    imr MARL 0
+    imr MARH 63
+    str R0
+    imr MARL 0
+    imr MARH 63
+    ld R0
+    push R0
+    ima 1
+    add SPL
+    amov SPL
+    ima 0
+    adc SPH
+    amov SPH
+    imr R0 1
+    rmov SPL
+    sub R0
+    amov MARL
+    imr R0 0
+    rmov SPH
+    sbc R0
+    amov MARH
+    ld R0
+    imr MARL 0
+    imr MARH 64
+    str R0
+    imr R0 1
+    rmov SPL
+    sub R0
+    amov SPL
+    imr R0 0
+    rmov SPH
+    sbc R0
+    amov SPH
+    hlt